@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use crate::core::DailyChallenge;
+use super::storage;
+
+const HISTORY_FILE: &str = "daily_history.json";
+
+/// Best result of one day
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub attempts: u32,
+}
+
+/// Local history of daily challenges, keyed by date (YYYY-MM-DD). Attempts that used
+/// placement hints are kept in their own table.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DailyHistory {
+    results: BTreeMap<String, DailyResult>,
+    #[serde(default)]
+    assisted: BTreeMap<String, DailyResult>,
+}
+
+impl DailyHistory {
+    pub fn load() -> Self {
+        storage::load_json(HISTORY_FILE).unwrap_or_default()
+    }
+
+    pub fn get(&self, date: &str, assisted: bool) -> Option<&DailyResult> {
+        self.table(assisted).get(date)
+    }
+
+    fn table(&self, assisted: bool) -> &BTreeMap<String, DailyResult> {
+        if assisted { &self.assisted } else { &self.results }
+    }
+
+    /// Records a finished attempt, keeping the best score of the day
+    pub fn record(&mut self, date: &str, score: u32, lines: u32, level: u32, assisted: bool) {
+        let table = if assisted { &mut self.assisted } else { &mut self.results };
+        let entry = table.entry(date.to_string()).or_insert(DailyResult {
+            score,
+            lines,
+            level,
+            attempts: 0,
+        });
+        entry.attempts += 1;
+        if score > entry.score {
+            *entry = DailyResult { score, lines, level, attempts: entry.attempts };
+        }
+        if let Err(err) = storage::save_json(HISTORY_FILE, self) {
+            eprintln!("Failed to save daily history: {err}");
+        }
+    }
+}
+
+/// Today's challenge, by the local calendar
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+pub fn challenge_for(date: NaiveDate) -> DailyChallenge {
+    DailyChallenge::for_date(date.year(), date.month(), date.day())
+}
+
+/// Days of the month containing `date`, with the weekday (0 = Monday) of the first one
+pub fn month_days(date: NaiveDate) -> (u32, Vec<NaiveDate>) {
+    let first = date.with_day(1).unwrap_or(date);
+    let days = first
+        .iter_days()
+        .take_while(|day| day.month() == first.month())
+        .collect();
+    (first.weekday().num_days_from_monday(), days)
+}
@@ -0,0 +1,51 @@
+use crate::core::{Bot, Game, GameEvent, GameMode, BagRandomizer, SeededRng};
+use crate::render::Animator;
+
+/// Seconds without input on the menu before the demo starts
+pub const IDLE_TIMEOUT: f64 = 20.0;
+
+const DEMO_PPS: f64 = 2.5;
+/// A bit short of perfect so games eventually end and start over
+const DEMO_SKILL: f64 = 0.9;
+
+/// Attract mode: the bot plays a Marathon game behind the menu, drawn by the normal renderer
+pub struct Demo {
+    pub game: Game,
+    pub animator: Animator,
+    bot: Bot,
+}
+
+impl Demo {
+    pub fn new() -> Self {
+        Self {
+            game: Game::new(GameMode::Marathon, BagRandomizer::new(), SeededRng::from_entropy()),
+            animator: Animator::new(),
+            bot: Bot::new(DEMO_PPS, DEMO_SKILL),
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        if self.game.end.is_some() {
+            *self = Self::new();
+            return;
+        }
+
+        self.bot.update(&mut self.game, dt);
+        self.game.update(dt);
+        for event in self.game.take_events() {
+            if let GameEvent::Locked { cleared, .. } = event {
+                self.animator.trigger_lock_pop();
+                if cleared > 0 {
+                    self.animator.trigger_line_clear();
+                }
+            }
+        }
+        self.animator.update(dt);
+    }
+}
+
+impl Default for Demo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
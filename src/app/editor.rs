@@ -0,0 +1,194 @@
+use std::fs;
+use std::io;
+
+use crate::core::{Board, Game, Layout, Puzzle, board::{BOARD_WIDTH, BOARD_HEIGHT, GARBAGE_COLOR}, fumen, layout, puzzle::PuzzleGoal, tetromino::TetrominoShape};
+use super::puzzles::USER_PUZZLE_DIR;
+use super::storage;
+
+/// Subdirectory of the data directory holding saved layouts
+pub const LAYOUT_DIR: &str = "layouts";
+
+/// Colors offered by the brush: empty, the seven pieces and garbage
+pub const BRUSHES: [u8; 9] = [0, 1, 2, 3, 4, 5, 6, 7, GARBAGE_COLOR];
+
+/// Puzzle goals the editor can export with, by name
+pub const GOALS: [&str; 4] = ["Clear lines", "Perfect clear", "T-spins", "Tetrises"];
+
+/// The board editor: a stack painted cell by cell and the pieces to play it with
+pub struct Editor {
+    pub board: Board,
+    /// Color painted by the primary button; the secondary button always erases
+    pub brush: u8,
+    /// Current piece, then the queue, as letters
+    pub pieces: String,
+    pub hold: Option<TetrominoShape>,
+    pub name: String,
+    /// Share code or fumen diagram shown for copying, or pasted for importing
+    pub code: String,
+    /// Pages of the last fumen diagram imported, and the one on the board
+    pub pages: Vec<Layout>,
+    pub page: usize,
+    /// Index into `GOALS` and its count, for exporting as a puzzle
+    pub goal: usize,
+    pub goal_count: u32,
+    /// Outcome of the last save, load or import
+    pub message: Option<Result<String, String>>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            board: Board::new(),
+            brush: GARBAGE_COLOR,
+            pieces: "TIOLJSZ".to_string(),
+            hold: None,
+            name: "My layout".to_string(),
+            code: String::new(),
+            pages: Vec::new(),
+            page: 0,
+            goal: 0,
+            goal_count: 1,
+            message: None,
+        }
+    }
+}
+
+impl Editor {
+    /// Paints one cell with the brush, or erases it
+    pub fn paint(&mut self, x: usize, y: usize, erase: bool) {
+        if x < BOARD_WIDTH && y < BOARD_HEIGHT {
+            self.board.grid[y][x] = if erase { 0 } else { self.brush };
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.board = Board::new();
+    }
+
+    /// The position as it stands, checked so it can be played
+    pub fn layout(&self) -> Result<Layout, String> {
+        let pieces = layout::parse_pieces(&self.name, &self.pieces)?;
+        let layout = Layout::new(&self.name, &self.board, &pieces, self.hold);
+        layout.sequence()?;
+        Ok(layout)
+    }
+
+    pub fn load(&mut self, layout: &Layout) -> Result<(), String> {
+        self.board = layout.build_board()?;
+        self.pieces = layout.pieces.clone();
+        self.hold = layout.hold;
+        self.name = layout.name.clone();
+        self.code = layout.to_code();
+        Ok(())
+    }
+
+    /// Copies a game's position, to study it or reproduce something that happened in it
+    pub fn load_game(&mut self, game: &Game) {
+        self.board = game.board.clone();
+        let mut pieces = vec![game.current_piece.shape];
+        if game.has_next() {
+            pieces.push(game.next_piece.shape);
+        }
+        if let Some(sequence) = &game.sequence {
+            pieces.extend(sequence.queue.iter().copied());
+        }
+        self.pieces = pieces.iter().map(|shape| shape.letter()).collect();
+        self.hold = game.hold_piece.as_ref().map(|piece| piece.shape);
+        self.name = format!("{} position", game.mode.name());
+        self.pages.clear();
+        self.message = None;
+    }
+
+    /// Names of the saved layouts, sorted
+    pub fn saved() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(storage::data_dir().join(LAYOUT_DIR)) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let layout = self.layout()?;
+        let file = format!("{}.json", file_name(&layout.name));
+        write_json(LAYOUT_DIR, &file, &layout).map_err(|err| format!("could not save layout: {err}"))
+    }
+
+    pub fn open(&mut self, name: &str) -> Result<(), String> {
+        let path = storage::data_dir().join(LAYOUT_DIR).join(format!("{name}.json"));
+        let text = fs::read_to_string(&path).map_err(|err| format!("could not read {}: {err}", path.display()))?;
+        let layout: Layout = serde_json::from_str(&text).map_err(|err| format!("bad layout file: {err}"))?;
+        self.pages.clear();
+        self.load(&layout)
+    }
+
+    /// Imports a share code, or every page of a fumen diagram, showing the first
+    pub fn import_code(&mut self) -> Result<(), String> {
+        if fumen::is_fumen(&self.code) {
+            self.pages = Layout::from_fumen(&self.code)?;
+            return self.show_page(0);
+        }
+        let layout = Layout::from_code(&self.code)?;
+        self.pages.clear();
+        self.load(&layout)
+    }
+
+    pub fn show_page(&mut self, page: usize) -> Result<(), String> {
+        let layout = self.pages.get(page).cloned().ok_or("no such page")?;
+        self.page = page;
+        self.load(&layout)
+    }
+
+    pub fn fumen(&self) -> Result<String, String> {
+        self.layout()?.to_fumen()
+    }
+
+    /// Writes the position as a puzzle to the user's puzzle directory. The queue becomes the
+    /// fixed sequence, with the held piece dealt first.
+    pub fn export_puzzle(&self) -> Result<String, String> {
+        let layout = self.layout()?;
+        let count = self.goal_count.max(1);
+        let goal = match self.goal {
+            0 => PuzzleGoal::ClearLines { lines: count },
+            1 => PuzzleGoal::PerfectClear,
+            2 => PuzzleGoal::TSpins { lines: 2, count },
+            _ => PuzzleGoal::Tetrises { count },
+        };
+        let id = file_name(&layout.name);
+        let pieces = layout.hold.map(|shape| shape.letter().to_string()).unwrap_or_default() + &layout.pieces;
+        let puzzle = Puzzle {
+            id: id.clone(),
+            name: layout.name,
+            description: String::new(),
+            board: layout.board,
+            pieces,
+            goal,
+        };
+        puzzle.validate()?;
+        write_json(USER_PUZZLE_DIR, &format!("{id}.json"), &puzzle).map_err(|err| format!("could not export puzzle: {err}"))?;
+        Ok(id)
+    }
+}
+
+/// A name made safe to use as a file name
+fn file_name(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    if cleaned.is_empty() { "layout".to_string() } else { cleaned }
+}
+
+fn write_json<T: serde::Serialize>(dir: &str, file: &str, value: &T) -> io::Result<()> {
+    let dir = storage::data_dir().join(dir);
+    fs::create_dir_all(&dir)?;
+    let text = serde_json::to_string_pretty(value).map_err(io::Error::other)?;
+    fs::write(dir.join(file), text)
+}
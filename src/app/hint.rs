@@ -0,0 +1,27 @@
+use crate::core::{Game, Piece, ai::{self, Weights}, board::{BOARD_WIDTH, BOARD_HEIGHT}, tetromino::TetrominoShape};
+
+/// The AI's suggestion for the current piece. Searching every placement is too slow to repeat
+/// each frame, so the result is kept until the board, the piece or the weights change.
+#[derive(Default)]
+pub struct HintCache {
+    key: Option<([[u8; BOARD_WIDTH]; BOARD_HEIGHT], TetrominoShape, [u64; Weights::COUNT])>,
+    placement: Option<Piece>,
+}
+
+impl HintCache {
+    /// Where the best placement puts the current piece, searched from its spawn position so the
+    /// hint stays put while the player moves the piece
+    pub fn get(&mut self, game: &Game, weights: &Weights) -> Option<&Piece> {
+        if !game.piece_active() {
+            return None;
+        }
+        let key = (game.board.grid, game.current_piece.shape, weights.to_array().map(f64::to_bits));
+        if self.key != Some(key) {
+            let spawn = game.spawn_piece(key.1);
+            self.placement = ai::best_placement(&game.board, &spawn, None, weights)
+                .map(|placement| placement.piece);
+            self.key = Some(key);
+        }
+        self.placement.as_ref()
+    }
+}
@@ -0,0 +1,88 @@
+use eframe::egui::{InputState, Key};
+use crate::core::Action;
+use crate::render::BoardTransform;
+
+impl Action {
+    /// Maps an action as seen on a transformed board back to the canonical board.
+    /// Mirroring swaps left/right and rotation direction; turning the view upside down swaps left/right only.
+    pub fn for_view(self, transform: BoardTransform) -> Action {
+        let swap_sides = transform.mirror != transform.upside_down;
+        match self {
+            Action::MoveLeft if swap_sides => Action::MoveRight,
+            Action::MoveRight if swap_sides => Action::MoveLeft,
+            Action::RotateCw if transform.mirror => Action::RotateCcw,
+            Action::RotateCcw if transform.mirror => Action::RotateCw,
+            other => other,
+        }
+    }
+}
+
+/// Keys bound to each action; an action may have several keys
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: Vec<(Action, Key)>,
+}
+
+impl KeyBindings {
+    /// Actions whose key was pressed this frame, in binding order
+    pub fn pressed(&self, input: &InputState) -> Vec<Action> {
+        let mut actions = Vec::new();
+        for &(action, key) in &self.bindings {
+            if input.key_pressed(key) && !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+        actions
+    }
+}
+
+impl KeyBindings {
+    /// Left half of the keyboard, for the first versus player
+    pub fn player_one() -> Self {
+        Self {
+            bindings: vec![
+                (Action::MoveLeft, Key::A),
+                (Action::MoveRight, Key::D),
+                (Action::SoftDrop, Key::S),
+                (Action::HardDrop, Key::Space),
+                (Action::RotateCw, Key::W),
+                (Action::RotateCcw, Key::Q),
+                (Action::Hold, Key::E),
+            ],
+        }
+    }
+
+    /// Arrow keys and the keys around Enter, for the second versus player
+    pub fn player_two() -> Self {
+        Self {
+            bindings: vec![
+                (Action::MoveLeft, Key::ArrowLeft),
+                (Action::MoveRight, Key::ArrowRight),
+                (Action::SoftDrop, Key::ArrowDown),
+                (Action::HardDrop, Key::Enter),
+                (Action::RotateCw, Key::ArrowUp),
+                (Action::RotateCcw, Key::Period),
+                (Action::Hold, Key::Slash),
+            ],
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (Action::MoveLeft, Key::ArrowLeft),
+                (Action::MoveRight, Key::ArrowRight),
+                (Action::SoftDrop, Key::ArrowDown),
+                (Action::HardDrop, Key::Space),
+                (Action::RotateCw, Key::ArrowUp),
+                (Action::RotateCw, Key::X),
+                (Action::RotateCcw, Key::Z),
+                (Action::Hold, Key::C),
+                (Action::Undo, Key::U),
+                (Action::Redo, Key::Y),
+            ],
+        }
+    }
+}
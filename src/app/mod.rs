@@ -1,133 +1,1054 @@
 pub mod state;
 pub mod settings;
+pub mod storage;
+pub mod zen;
+pub mod puzzles;
+pub mod input;
+pub mod daily;
+pub mod versus;
+pub mod hint;
+pub mod demo;
+pub mod editor;
+pub mod replay;
+pub mod replay_viewer;
+pub mod saved_game;
 
+use std::path::PathBuf;
+
+use chrono::{DateTime, Datelike, Local, Months, NaiveDate};
 use eframe::egui;
-use crate::core::{Board, Piece, Rules, BagRandomizer};
-use crate::render::{Theme, draw_board, draw_panels, Animator};
+use rand::Rng;
+use crate::core::{Bot, Game, GameEvent, GameEnd, Piece, BagRandomizer, SeededRng, GameMode, PuzzleRun, DailyChallenge, Layout, TSpin, game::FRAME, tetromino::TetrominoShape};
+use crate::render::{Theme, BoardTransform, draw_board, draw_panels, Animator};
 use crate::render::theme::ThemeType;
-use crate::time::Clock;
+use crate::tbp::ExternalPlayer;
 use state::GameState;
 use settings::Settings;
+use zen::{ZenSession, ZenStats};
+use puzzles::{PuzzlePack, PuzzlePlay};
+use daily::DailyHistory;
+use versus::{VersusMatch, RoundResult};
+use hint::HintCache;
+use demo::Demo;
+use editor::Editor;
+use replay::{Recording, Replay, ReplayStart};
+use replay_viewer::ReplayViewer;
+use saved_game::SavedGame;
 
 pub struct TetrisApp {
-    pub board: Board,
-    pub current_piece: Piece,
-    pub next_piece: Piece,
-    pub hold_piece: Option<Piece>,
-    pub can_hold: bool,
+    pub game: Game,
     pub state: GameState,
+    /// Mode selected in the menu
+    pub mode: GameMode,
     pub settings: Settings,
-    pub clock: Clock,
-    pub rng: BagRandomizer,
     pub animator: Animator,
-    pub score: u32,
-    pub level: u32,
-    pub lines: u32,
-    pub lock_timer: Option<f64>,
+    pub zen_stats: ZenStats,
+    pub puzzles: PuzzlePack,
+    pub puzzle: Option<PuzzlePlay>,
+    /// Set while playing a daily challenge
+    pub daily: Option<DailyChallenge>,
+    pub daily_history: DailyHistory,
+    /// Month shown in the daily calendar
+    pub calendar_month: NaiveDate,
+    pub versus: Option<VersusMatch>,
+    pub hint: HintCache,
+    /// Set while the settings screen waits for a new hint key
+    pub rebinding_hint: bool,
+    pub demo: Option<Demo>,
+    /// Seconds the menu has gone without input
+    pub idle_time: f64,
+    /// External bot playing the game in place of the keyboard
+    pub external: Option<ExternalPlayer>,
+    /// Why the external bot stopped or could not start
+    pub bot_error: Option<String>,
+    /// Seeds and setup of the game in play, saved as a replay when it ends
+    pub recording: Option<Recording>,
+    /// Frame time not yet stepped; the engine runs at a fixed rate so replays match
+    pub accumulator: f64,
+    /// Saved replays, newest first, as listed when the replay screen opened
+    pub replays: Vec<(PathBuf, Replay)>,
+    pub viewer: Option<ReplayViewer>,
+    /// Why the chosen replay could not be played
+    pub replay_error: Option<String>,
+    pub editor: Editor,
+    /// Layouts saved from the editor, as listed when it opened
+    pub saved_layouts: Vec<String>,
 }
 
 impl TetrisApp {
     pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        let mut rng = BagRandomizer::new();
-        let current_piece = Piece::new(rng.next());
-        let next_piece = Piece::new(rng.next());
-
+        let mut settings = Settings::default();
+        // Tuned weights replace the built-in ones when present
+        if let Some(weights) = storage::load_json(settings::AI_WEIGHTS_FILE) {
+            settings.ai_weights = weights;
+        }
         Self {
-            board: Board::new(),
-            current_piece,
-            next_piece,
-            hold_piece: None,
-            can_hold: true,
+            game: Game::new(GameMode::Marathon, BagRandomizer::new(), SeededRng::from_entropy()),
             state: GameState::Menu,
-            settings: Settings::default(),
-            clock: Clock::new(0),
-            rng,
+            mode: GameMode::Marathon,
+            settings,
             animator: Animator::new(),
-            score: 0,
-            level: 0,
-            lines: 0,
-            lock_timer: None,
+            zen_stats: ZenStats::default(),
+            puzzles: PuzzlePack::load(),
+            puzzle: None,
+            daily: None,
+            daily_history: DailyHistory::load(),
+            calendar_month: daily::today(),
+            versus: None,
+            hint: HintCache::default(),
+            rebinding_hint: false,
+            demo: None,
+            idle_time: 0.0,
+            external: None,
+            bot_error: None,
+            recording: None,
+            accumulator: 0.0,
+            replays: Vec::new(),
+            viewer: None,
+            replay_error: None,
+            editor: Editor::default(),
+            saved_layouts: Vec::new(),
         }
     }
 
     pub fn reset(&mut self) {
-        self.board = Board::new();
-        self.rng = BagRandomizer::new();
-        self.current_piece = Piece::new(self.rng.next());
-        self.next_piece = Piece::new(self.rng.next());
-        self.hold_piece = None;
-        self.can_hold = true;
-        self.score = 0;
-        self.level = 0;
-        self.lines = 0;
-        self.lock_timer = None;
-        self.clock = Clock::new(0);
+        let puzzle_index = self.puzzle.take().map(|play| play.index);
+        let (seed, garbage_seed, start) = match &self.daily {
+            Some(challenge) => {
+                self.mode = challenge.mode;
+                (challenge.seed, challenge.garbage_seed(), ReplayStart::Daily(challenge.date.clone()))
+            }
+            None => {
+                let mut rng = rand::thread_rng();
+                (rng.gen(), rng.gen(), ReplayStart::Fresh)
+            }
+        };
+        self.game = Game::new(self.mode, BagRandomizer::with_seed(seed), SeededRng::new(garbage_seed));
+        self.recording = Some(Recording { seed, garbage_seed, start });
+        self.accumulator = 0.0;
         self.state = GameState::Playing;
+        self.external = None;
+
+        match self.mode {
+            GameMode::Zen => {
+                if let Some(session) = ZenSession::load() {
+                    self.restore_zen(session);
+                    // A resumed session can't be replayed from its seeds
+                    self.recording = None;
+                }
+            }
+            // Restarting replays the same puzzle
+            GameMode::Puzzle => {
+                if let Some(index) = puzzle_index {
+                    self.load_puzzle(index);
+                }
+            }
+            GameMode::Sandbox => self.load_layout(seed, garbage_seed),
+            _ => {}
+        }
     }
 
-    fn spawn_next_piece(&mut self) {
-        self.current_piece = Piece::new(self.next_piece.shape);
-        self.next_piece = Piece::new(self.rng.next());
-        self.can_hold = true;
+    fn open_editor(&mut self) {
+        self.saved_layouts = Editor::saved();
+        self.editor.message = None;
+        self.state = GameState::Editor;
+    }
 
-        if !self.board.can_place(&self.current_piece) {
-            self.state = GameState::GameOver;
+    /// Plays the editor's position in Sandbox mode
+    fn start_sandbox(&mut self) {
+        if let Err(err) = self.editor.layout() {
+            self.editor.message = Some(Err(err));
+            return;
         }
+        self.daily = None;
+        self.puzzle = None;
+        self.mode = GameMode::Sandbox;
+        self.reset();
     }
 
-    fn hold(&mut self) {
-        if !self.can_hold {
+    /// Sets up the game from the editor's position; the randomizer deals once its pieces run out
+    fn load_layout(&mut self, seed: u64, garbage_seed: u64) {
+        let Ok(layout) = self.editor.layout() else {
             return;
+        };
+        if let Ok(game) = Game::from_layout(GameMode::Sandbox, &layout, BagRandomizer::with_seed(seed), SeededRng::new(garbage_seed)) {
+            self.game = game;
+            self.recording = Some(Recording { seed, garbage_seed, start: ReplayStart::Layout(layout.to_code()) });
         }
-        self.can_hold = false;
+    }
 
-        if let Some(held) = self.hold_piece.take() {
-            let current_shape = self.current_piece.shape;
-            self.current_piece = Piece::new(held.shape);
-            self.hold_piece = Some(Piece::new(current_shape));
+    fn start_daily(&mut self, challenge: DailyChallenge) {
+        self.puzzle = None;
+        self.daily = Some(challenge);
+        self.reset();
+    }
+
+    fn start_versus(&mut self, against_bot: bool) {
+        let settings = &self.settings;
+        let bot = against_bot.then(|| Bot::new(settings.ai_pps, settings.ai_skill).with_weights(settings.ai_weights));
+        self.versus = Some(VersusMatch::new(settings.versus_best_of, settings.versus_same_seed, bot));
+        self.state = GameState::Versus;
+    }
+
+    /// Starts a Marathon game played by the external bot from the settings
+    fn start_external(&mut self) {
+        self.daily = None;
+        self.mode = GameMode::Marathon;
+        self.bot_error = None;
+        match ExternalPlayer::spawn(&self.settings.tbp_command) {
+            Ok(player) => {
+                self.reset();
+                self.external = Some(player);
+            }
+            Err(err) => self.bot_error = Some(err),
+        }
+    }
+
+    /// Plays again, with a fresh external bot if one was playing
+    fn restart(&mut self) {
+        if self.external.is_some() {
+            self.start_external();
         } else {
-            self.hold_piece = Some(Piece::new(self.current_piece.shape));
-            self.spawn_next_piece();
+            self.reset();
         }
     }
 
-    fn lock_piece(&mut self) {
-        self.board.lock_piece(&self.current_piece);
-        let cleared = self.board.clear_lines();
+    /// Ends a game that can be lost, recording the daily result if there is one
+    fn game_over(&mut self) {
+        if let Some(challenge) = &self.daily {
+            self.daily_history.record(&challenge.date, self.game.score, self.game.lines, self.game.level, self.game.assisted);
+        }
+        self.save_replay();
+        self.state = GameState::GameOver;
+    }
 
-        if cleared > 0 {
-            self.lines += cleared;
-            self.score += Rules::score_for_lines(cleared, self.level);
-            self.level = self.lines / 10;
-            self.clock.set_level(self.level);
-            self.animator.trigger_line_clear();
+    /// Writes the game's replay, once, if it is being recorded
+    fn save_replay(&mut self) {
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+        if let Err(err) = recording.finish(&self.game, &self.settings).save() {
+            eprintln!("Failed to save replay: {err}");
         }
+    }
 
-        self.animator.trigger_lock_pop();
-        self.lock_timer = None;
-        self.spawn_next_piece();
+    fn start_puzzle(&mut self, index: usize) {
+        self.puzzle = None;
+        self.reset();
+        self.load_puzzle(index);
     }
 
-    fn hard_drop(&mut self) {
-        let cells_dropped = self.board.drop_distance(&self.current_piece);
-        self.current_piece.y += cells_dropped as i32;
-        self.score += (cells_dropped as u32) * 2;
-        self.lock_piece();
+    fn load_puzzle(&mut self, index: usize) {
+        let puzzle = self.puzzles.puzzles[index].clone();
+        // Puzzles are validated when the pack is loaded
+        let (Ok(board), Ok(sequence)) = (puzzle.build_board(), puzzle.sequence()) else {
+            return;
+        };
+        if sequence.is_empty() {
+            return;
+        }
+
+        self.game = Game::with_sequence(GameMode::Puzzle, board, sequence);
+        self.recording = Some(Recording { seed: 0, garbage_seed: 0, start: ReplayStart::Puzzle(puzzle.id.clone()) });
+        self.puzzle = Some(PuzzlePlay {
+            index,
+            puzzle,
+            run: PuzzleRun::default(),
+            solved: None,
+        });
+    }
+
+    fn finish_puzzle(&mut self, solved: bool) {
+        if let Some(play) = &mut self.puzzle {
+            play.solved = Some(solved);
+            // A solve with hints doesn't count towards progress
+            if solved && !self.game.assisted {
+                self.puzzles.mark_solved(play.index);
+            }
+        }
+        self.save_replay();
+        self.state = GameState::Results;
+    }
+
+    fn restore_zen(&mut self, session: ZenSession) {
+        let game = &mut self.game;
+        game.board = session.board;
+        game.current_piece = game.spawn_piece(session.current);
+        game.next_piece = Piece::new(session.next);
+        game.hold_piece = session.hold.map(Piece::new);
+        game.score = session.score;
+        game.level = session.level;
+        game.lines = session.lines;
+        self.zen_stats = session.stats;
+    }
+
+    fn save_zen(&self) {
+        if self.mode != GameMode::Zen {
+            return;
+        }
+        let game = &self.game;
+        ZenSession {
+            board: game.board.clone(),
+            current: game.current_piece.shape,
+            next: game.next_piece.shape,
+            hold: game.hold_piece.as_ref().map(|p| p.shape),
+            score: game.score,
+            level: game.level,
+            lines: game.lines,
+            stats: self.zen_stats.clone(),
+        }
+        .save();
+    }
+
+    /// Whether the game in play can be put aside with Save & Quit. Zen saves itself, puzzles
+    /// are short and external bots can't be restored.
+    fn can_save(&self) -> bool {
+        !matches!(self.game.mode, GameMode::Zen | GameMode::Puzzle) && self.external.is_none() && self.game.end.is_none()
     }
 
-    fn soft_drop(&mut self) {
-        if self.board.can_move(&self.current_piece, 0, 1) {
-            self.current_piece.y += 1;
-            self.score += 1;
+    /// Writes the whole game to disk and leaves for the menu; Continue restores it
+    fn save_and_quit(&mut self) {
+        SavedGame {
+            game: self.game.clone(),
+            daily: self.daily.clone(),
+            recording: self.recording.take(),
+            accumulator: self.accumulator,
+        }
+        .save();
+        self.state = GameState::Menu;
+    }
+
+    fn continue_game(&mut self) {
+        let Some(saved) = SavedGame::load() else {
+            return;
+        };
+        SavedGame::clear();
+        self.mode = saved.game.mode;
+        self.game = saved.game;
+        self.daily = saved.daily;
+        self.recording = saved.recording;
+        self.accumulator = saved.accumulator;
+        self.puzzle = None;
+        self.external = None;
+        self.state = GameState::Paused;
+    }
+
+    /// Leaves the current game for the menu, keeping the Zen session on disk
+    fn quit_to_menu(&mut self) {
+        self.save_zen();
+        self.save_replay();
+        self.external = None;
+        self.state = GameState::Menu;
+    }
+
+    fn pause(&mut self) {
+        self.save_zen();
+        self.state = GameState::Paused;
+    }
+
+    /// Feeds animations and mode bookkeeping from the game's events, then handles the end of the game
+    fn handle_game_events(&mut self) {
+        for event in self.game.take_events() {
+            match event {
+                GameEvent::Locked { cleared, t_spin } => {
+                    self.animator.trigger_lock_pop();
+                    if cleared > 0 {
+                        self.animator.trigger_line_clear();
+                        if self.mode.hides_stack() {
+                            self.animator.trigger_stack_outline();
+                        }
+                    }
+                    if self.mode == GameMode::Zen {
+                        self.zen_stats.record_lock(cleared);
+                    }
+                    if let Some(play) = &mut self.puzzle {
+                        play.run.record_lock(cleared, t_spin);
+                    }
+                }
+                GameEvent::BoardReset => {
+                    self.zen_stats.board_resets += 1;
+                    self.animator.trigger_line_clear();
+                    self.save_zen();
+                }
+                GameEvent::Placed { .. } | GameEvent::Attack(_) => {}
+            }
+        }
+
+        let Some(end) = self.game.end else {
+            return;
+        };
+        match (self.mode, end) {
+            (GameMode::Master, _) => {
+                self.save_replay();
+                self.state = GameState::Results;
+            }
+            (GameMode::Puzzle, GameEnd::OutOfPieces) => {
+                let solved = self
+                    .puzzle
+                    .as_ref()
+                    .is_some_and(|play| play.run.is_solved(&play.puzzle.goal, &self.game.board));
+                self.finish_puzzle(solved);
+            }
+            (GameMode::Puzzle, _) => self.finish_puzzle(false),
+            _ => self.game_over(),
         }
     }
 
     fn get_theme(&self) -> Theme {
         Theme::new(self.settings.theme)
     }
+
+    fn draw_master_results(&self, ui: &mut egui::Ui, theme: &Theme) {
+        let title = if self.game.master.is_complete() { "🏆 MASTER CLEAR" } else { "🏁 RESULTS" };
+        ui.label(egui::RichText::new(title)
+            .size(28.0)
+            .color(theme.text_primary)
+            .strong());
+        ui.add_space(20.0);
+
+        egui::Frame::none()
+            .fill(theme.panel)
+            .rounding(egui::Rounding::same(8.0))
+            .inner_margin(20.0)
+            .show(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(egui::RichText::new("GRADE")
+                        .size(12.0)
+                        .color(theme.text_secondary));
+                    ui.label(egui::RichText::new(self.game.master.final_grade())
+                        .size(48.0)
+                        .color(theme.text_primary)
+                        .strong());
+                    ui.add_space(8.0);
+                    ui.label(egui::RichText::new(format!(
+                        "Level {} · {} · Score {}",
+                        self.game.level,
+                        format_time(self.game.master.elapsed()),
+                        self.game.score
+                    ))
+                        .size(15.0)
+                        .color(theme.text_secondary));
+                    ui.add_space(12.0);
+
+                    // Section times
+                    egui::Grid::new("section_times").striped(true).show(ui, |ui| {
+                        for (i, time) in self.game.master.section_times().iter().enumerate() {
+                            ui.label(egui::RichText::new(format!("{:03}–{:03}", i * 100, i * 100 + 99))
+                                .size(13.0)
+                                .color(theme.text_secondary));
+                            ui.label(egui::RichText::new(format_time(*time))
+                                .size(13.0)
+                                .color(theme.text_primary));
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
+
+    fn draw_puzzle_results(&self, ui: &mut egui::Ui, theme: &Theme) {
+        let Some(play) = &self.puzzle else {
+            return;
+        };
+        let title = if play.solved == Some(true) { "✔ SOLVED" } else { "✖ FAILED" };
+        ui.label(egui::RichText::new(title)
+            .size(28.0)
+            .color(theme.text_primary)
+            .strong());
+        ui.add_space(20.0);
+
+        egui::Frame::none()
+            .fill(theme.panel)
+            .rounding(egui::Rounding::same(8.0))
+            .inner_margin(20.0)
+            .show(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(egui::RichText::new(&play.puzzle.name)
+                        .size(20.0)
+                        .color(theme.text_primary)
+                        .strong());
+                    ui.add_space(5.0);
+                    ui.label(egui::RichText::new(format!("Goal: {}", play.puzzle.goal.description()))
+                        .size(15.0)
+                        .color(theme.text_secondary));
+                    ui.label(egui::RichText::new(format!("Lines cleared: {}", play.run.lines))
+                        .size(15.0)
+                        .color(theme.text_secondary));
+                });
+            });
+    }
+
+    /// Two wells side by side, each with a compact panel, and the round and match results on top
+    fn update_versus(&mut self, ctx: &egui::Context, theme: &Theme) {
+        let Some(versus) = &mut self.versus else {
+            self.state = GameState::Menu;
+            return;
+        };
+
+        let mut quit = false;
+        ctx.input(|i| {
+            if versus.bot.is_some() {
+                // Alone against the bot, the player keeps the usual keys
+                for action in self.settings.keys.pressed(i) {
+                    versus.apply(0, action);
+                }
+            } else {
+                for (player, keys) in self.settings.versus_keys.iter().enumerate() {
+                    for action in keys.pressed(i) {
+                        versus.apply(player, action);
+                    }
+                }
+            }
+            if versus.round_result.is_some()
+                && versus.match_winner().is_none()
+                && i.key_pressed(egui::Key::Enter)
+            {
+                versus.next_round();
+            }
+            if i.key_pressed(egui::Key::Escape) {
+                quit = true;
+            }
+        });
+        let dt = ctx.input(|i| i.stable_dt as f64);
+        versus.update(dt);
+
+        let mut rematch = false;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let well = draw_board::well_size();
+            for player in 0..2 {
+                let origin = egui::Pos2::new(40.0 + player as f32 * 380.0, 60.0);
+                draw_board::render(ui, theme, &versus.games[player], &versus.animators[player], &self.settings, None, origin);
+
+                let panel = egui::Rect::from_min_size(
+                    origin + egui::Vec2::new(well.x + 12.0, 0.0),
+                    egui::Vec2::new(110.0, well.y),
+                );
+                ui.allocate_new_ui(egui::UiBuilder::new().max_rect(panel), |ui| {
+                    let name = versus.player_name(player);
+                    draw_panels::render_versus(ui, theme, &versus.games[player], &name, versus.wins[player], versus.wins_needed());
+                });
+            }
+
+            let Some(result) = versus.round_result else {
+                return;
+            };
+            let message = match (result, versus.match_winner()) {
+                (_, Some(winner)) => format!("🏆 {} WINS THE MATCH", versus.player_name(winner)),
+                (RoundResult::Winner(winner), None) => format!("{} WINS THE ROUND", versus.player_name(winner)),
+                (RoundResult::Draw, None) => "DRAW".to_string(),
+            };
+            egui::Area::new(egui::Id::new("versus_result"))
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    egui::Frame::none()
+                        .fill(theme.panel)
+                        .rounding(egui::Rounding::same(8.0))
+                        .inner_margin(20.0)
+                        .show(ui, |ui| {
+                            ui.vertical_centered(|ui| {
+                                ui.label(egui::RichText::new(message)
+                                    .size(24.0)
+                                    .color(theme.text_primary)
+                                    .strong());
+                                ui.label(egui::RichText::new(format!("{} – {}", versus.wins[0], versus.wins[1]))
+                                    .size(18.0)
+                                    .color(theme.text_secondary));
+                                ui.add_space(12.0);
+                                if versus.match_winner().is_some() {
+                                    if ui.button("🔄 Rematch").clicked() {
+                                        rematch = true;
+                                    }
+                                } else if ui.button("▶ Next Round (Enter)").clicked() {
+                                    versus.next_round();
+                                }
+                                if ui.button("🏠 Menu").clicked() {
+                                    quit = true;
+                                }
+                            });
+                        });
+                });
+        });
+
+        if rematch {
+            let against_bot = self.versus.as_ref().is_some_and(|versus| versus.bot.is_some());
+            self.start_versus(against_bot);
+        }
+        if quit {
+            self.versus = None;
+            self.state = GameState::Menu;
+        }
+    }
+
+    fn update_demo(&mut self, ctx: &egui::Context, theme: &Theme) {
+        // Any key or click hands control back to the menu
+        let interrupted = ctx.input(|i| {
+            i.events.iter().any(|event| {
+                matches!(event, egui::Event::Key { pressed: true, .. } | egui::Event::PointerButton { pressed: true, .. })
+            })
+        });
+        let Some(demo) = self.demo.as_mut().filter(|_| !interrupted) else {
+            self.demo = None;
+            self.idle_time = 0.0;
+            self.state = GameState::Menu;
+            return;
+        };
+
+        demo.update(ctx.input(|i| i.stable_dt as f64));
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            draw_board::render(ui, theme, &demo.game, &demo.animator, &self.settings, None, draw_board::BOARD_OFFSET);
+        });
+        egui::SidePanel::right("info_panel")
+            .min_width(140.0)
+            .show(ctx, |ui| {
+                draw_panels::render(ui, theme, &demo.game, None);
+            });
+        egui::Area::new(egui::Id::new("demo_banner"))
+            .anchor(egui::Align2::CENTER_TOP, egui::Vec2::new(0.0, 12.0))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("DEMO · press any key")
+                    .size(18.0)
+                    .color(theme.text_primary)
+                    .strong());
+            });
+    }
+
+    fn open_replays(&mut self) {
+        self.replays = replay::list();
+        self.replay_error = None;
+        self.state = GameState::ReplaySelect;
+    }
+
+    fn update_replay_select(&mut self, ctx: &egui::Context, theme: &Theme) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(40.0);
+                ui.label(egui::RichText::new("🎞 REPLAYS")
+                    .size(28.0)
+                    .color(theme.text_primary)
+                    .strong());
+                ui.add_space(20.0);
+
+                if self.replays.is_empty() {
+                    ui.label(egui::RichText::new("No replays yet. Every single-player game is recorded.")
+                        .size(13.0)
+                        .color(theme.text_secondary));
+                }
+
+                let mut selected = None;
+                egui::ScrollArea::vertical().max_height(380.0).show(ui, |ui| {
+                    for (index, (_, replay)) in self.replays.iter().enumerate() {
+                        egui::Frame::none()
+                            .fill(theme.panel)
+                            .rounding(egui::Rounding::same(8.0))
+                            .inner_margin(10.0)
+                            .show(ui, |ui| {
+                                ui.set_width(420.0);
+                                ui.horizontal(|ui| {
+                                    ui.vertical(|ui| {
+                                        let title = match &replay.start {
+                                            ReplayStart::Fresh => replay.mode.name().to_string(),
+                                            ReplayStart::Daily(date) => format!("Daily {date}"),
+                                            ReplayStart::Puzzle(id) => format!("Puzzle {id}"),
+                                            ReplayStart::Layout(code) => match Layout::from_code(code) {
+                                                Ok(layout) => format!("Sandbox · {}", layout.name),
+                                                Err(_) => "Sandbox".to_string(),
+                                            },
+                                        };
+                                        ui.label(egui::RichText::new(title)
+                                            .size(15.0)
+                                            .color(theme.text_primary)
+                                            .strong());
+                                        let when = DateTime::from_timestamp(replay.recorded_at, 0)
+                                            .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                                            .unwrap_or_default();
+                                        ui.label(egui::RichText::new(format!(
+                                            "{when} · Score {} · {} lines · {}",
+                                            replay.score,
+                                            replay.lines,
+                                            format_time(replay.frames as f64 * FRAME)
+                                        ))
+                                            .size(11.0)
+                                            .color(theme.text_secondary));
+                                    });
+                                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                        if ui.button("▶ Watch").clicked() {
+                                            selected = Some(index);
+                                        }
+                                    });
+                                });
+                            });
+                        ui.add_space(6.0);
+                    }
+                });
+                if let Some(index) = selected {
+                    let replay = self.replays[index].1.clone();
+                    match ReplayViewer::new(replay, &self.puzzles, &self.settings) {
+                        Ok(viewer) => {
+                            self.viewer = Some(viewer);
+                            self.state = GameState::Replay;
+                        }
+                        Err(err) => self.replay_error = Some(err),
+                    }
+                }
+                if let Some(err) = &self.replay_error {
+                    ui.label(egui::RichText::new(err)
+                        .size(12.0)
+                        .color(theme.danger));
+                }
+
+                ui.add_space(20.0);
+                if ui.add(egui::Button::new(
+                    egui::RichText::new("← Back to Menu").size(14.0)
+                ).min_size(egui::Vec2::new(140.0, 36.0))).clicked() {
+                    self.state = GameState::Menu;
+                }
+            });
+        });
+
+        ctx.input(|i| {
+            if i.key_pressed(egui::Key::Escape) {
+                self.state = GameState::Menu;
+            }
+        });
+    }
+
+    fn update_replay(&mut self, ctx: &egui::Context) {
+        let Some(viewer) = self.viewer.as_mut() else {
+            self.state = GameState::ReplaySelect;
+            return;
+        };
+        // Watched with the look it was played with
+        let theme = Theme::new(viewer.settings.theme);
+
+        let mut leave = false;
+        ctx.input(|i| {
+            if i.key_pressed(egui::Key::Space) {
+                viewer.playing = !viewer.playing;
+            }
+            if i.key_pressed(egui::Key::ArrowRight) {
+                viewer.playing = false;
+                viewer.step_frame();
+            }
+            if i.key_pressed(egui::Key::ArrowLeft) {
+                viewer.playing = false;
+                viewer.seek(viewer.game.frame.saturating_sub(1));
+            }
+            if i.key_pressed(egui::Key::ArrowUp) {
+                viewer.speed = replay_viewer::SPEEDS.into_iter().find(|&speed| speed > viewer.speed).unwrap_or(viewer.speed);
+            }
+            if i.key_pressed(egui::Key::ArrowDown) {
+                viewer.speed = replay_viewer::SPEEDS.into_iter().rev().find(|&speed| speed < viewer.speed).unwrap_or(viewer.speed);
+            }
+            if i.key_pressed(egui::Key::Escape) {
+                leave = true;
+            }
+        });
+        viewer.update(ctx.input(|i| i.stable_dt as f64));
+
+        egui::TopBottomPanel::bottom("replay_controls").show(ctx, |ui| {
+            ui.add_space(6.0);
+            if let Some(frame) = draw_timeline(ui, &theme, viewer) {
+                viewer.seek(frame);
+            }
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if ui.button("⏮").on_hover_text("Back to the start").clicked() {
+                    viewer.seek(0);
+                }
+                if ui.button("◀|").on_hover_text("Previous frame (←)").clicked() {
+                    viewer.playing = false;
+                    viewer.seek(viewer.game.frame.saturating_sub(1));
+                }
+                let play_label = if viewer.playing { "⏸" } else { "▶" };
+                if ui.button(play_label).on_hover_text("Play / pause (Space)").clicked() {
+                    if viewer.finished() {
+                        viewer.seek(0);
+                    }
+                    viewer.playing = !viewer.playing;
+                }
+                if ui.button("|▶").on_hover_text("Next frame (→)").clicked() {
+                    viewer.playing = false;
+                    viewer.step_frame();
+                }
+                ui.separator();
+                for speed in replay_viewer::SPEEDS {
+                    ui.selectable_value(&mut viewer.speed, speed, format!("{speed}×"));
+                }
+                ui.separator();
+                if ui.button("⟦ Mark").on_hover_text("Start the fumen export here").clicked() {
+                    viewer.segment_start = viewer.game.frame;
+                }
+                if ui.button("📋 Fumen").on_hover_text("Copy the game from the mark to here as a fumen diagram").clicked() {
+                    let (from, to) = (viewer.segment_start.min(viewer.game.frame), viewer.segment_start.max(viewer.game.frame));
+                    ctx.copy_text(viewer.fumen(from, to));
+                    viewer.notice = Some(format!(
+                        "Fumen of {}–{} copied",
+                        format_time(from as f64 * FRAME),
+                        format_time(to as f64 * FRAME)
+                    ));
+                }
+                if let Some(notice) = &viewer.notice {
+                    ui.label(egui::RichText::new(notice).size(12.0).color(theme.text_secondary));
+                }
+                ui.separator();
+                ui.label(egui::RichText::new(format!(
+                    "{} / {} · frame {}",
+                    format_time(viewer.game.frame as f64 * FRAME),
+                    format_time(viewer.frames() as f64 * FRAME),
+                    viewer.game.frame
+                ))
+                    .size(13.0)
+                    .color(theme.text_secondary));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("✖ Close").clicked() {
+                        leave = true;
+                    }
+                });
+            });
+            ui.add_space(6.0);
+        });
+        egui::SidePanel::right("info_panel")
+            .min_width(140.0)
+            .show(ctx, |ui| {
+                draw_panels::render(ui, &theme, &viewer.game, None);
+            });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            draw_board::render(ui, &theme, &viewer.game, &viewer.animator, &viewer.settings, None, draw_board::BOARD_OFFSET);
+        });
+
+        if leave {
+            self.viewer = None;
+            self.state = GameState::ReplaySelect;
+        }
+    }
+
+    fn update_editor(&mut self, ctx: &egui::Context, theme: &Theme) {
+        let mut play = false;
+        egui::SidePanel::right("editor_panel")
+            .min_width(260.0)
+            .show(ctx, |ui| {
+                let editor = &mut self.editor;
+                ui.add_space(10.0);
+                ui.label(egui::RichText::new("🖌 BOARD EDITOR")
+                    .size(20.0)
+                    .color(theme.text_primary)
+                    .strong());
+                ui.label(egui::RichText::new("Left button paints, right button erases")
+                    .size(11.0)
+                    .color(theme.text_secondary));
+                ui.add_space(8.0);
+
+                ui.horizontal_wrapped(|ui| {
+                    for brush in editor::BRUSHES {
+                        let label = match brush {
+                            0 => "·".to_string(),
+                            _ => TetrominoShape::from_color_id(brush).map_or('G', |shape| shape.letter()).to_string(),
+                        };
+                        let fill = if brush == 0 { theme.background } else { theme.block_colors[brush as usize] };
+                        let stroke = if editor.brush == brush { 3.0 } else { 0.0 };
+                        let button = egui::Button::new(egui::RichText::new(label).color(egui::Color32::BLACK).strong())
+                            .fill(fill)
+                            .stroke(egui::Stroke::new(stroke, theme.text_primary))
+                            .min_size(egui::Vec2::new(24.0, 24.0));
+                        if ui.add(button).clicked() {
+                            editor.brush = brush;
+                        }
+                    }
+                });
+                ui.add_space(8.0);
+
+                ui.label(egui::RichText::new("Pieces (current, then queue)").size(12.0).color(theme.text_secondary));
+                ui.text_edit_singleline(&mut editor.pieces);
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Hold").size(12.0).color(theme.text_secondary));
+                    let hold_label = editor.hold.map_or("None".to_string(), |shape| shape.letter().to_string());
+                    egui::ComboBox::from_id_salt("editor_hold")
+                        .selected_text(hold_label)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut editor.hold, None, "None");
+                            for shape in TetrominoShape::ALL {
+                                ui.selectable_value(&mut editor.hold, Some(shape), shape.letter().to_string());
+                            }
+                        });
+                });
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    if ui.button("▶ Play").clicked() {
+                        play = true;
+                    }
+                    if ui.button("🗑 Clear board").clicked() {
+                        editor.clear();
+                    }
+                });
+
+                ui.separator();
+                ui.label(egui::RichText::new("Layout name").size(12.0).color(theme.text_secondary));
+                ui.text_edit_singleline(&mut editor.name);
+                ui.horizontal(|ui| {
+                    if ui.button("💾 Save").clicked() {
+                        editor.message = Some(editor.save().map(|()| format!("Saved {}", editor.name)));
+                        self.saved_layouts = Editor::saved();
+                    }
+                    let mut opened = None;
+                    egui::ComboBox::from_id_salt("editor_open")
+                        .selected_text("📂 Open")
+                        .show_ui(ui, |ui| {
+                            for name in &self.saved_layouts {
+                                if ui.selectable_label(false, name).clicked() {
+                                    opened = Some(name.clone());
+                                }
+                            }
+                        });
+                    if let Some(name) = opened {
+                        editor.message = Some(editor.open(&name).map(|()| format!("Opened {name}")));
+                    }
+                });
+
+                ui.add_space(6.0);
+                ui.label(egui::RichText::new("Share code or fumen").size(12.0).color(theme.text_secondary));
+                ui.text_edit_singleline(&mut editor.code);
+                ui.horizontal(|ui| {
+                    if ui.button("📋 Copy code").clicked() {
+                        editor.message = Some(editor.layout().map(|layout| {
+                            editor.code = layout.to_code();
+                            ctx.copy_text(editor.code.clone());
+                            "Code copied".to_string()
+                        }));
+                    }
+                    if ui.button("📋 Copy fumen").clicked() {
+                        editor.message = Some(editor.fumen().map(|fumen| {
+                            editor.code = fumen;
+                            ctx.copy_text(editor.code.clone());
+                            "Fumen copied".to_string()
+                        }));
+                    }
+                    if ui.button("📥 Import").clicked() {
+                        editor.message = Some(editor.import_code().map(|()| format!("Imported {}", editor.name)));
+                    }
+                });
+                if editor.pages.len() > 1 {
+                    ui.horizontal(|ui| {
+                        let mut page = editor.page;
+                        if ui.add_enabled(page > 0, egui::Button::new("◀")).clicked() {
+                            page -= 1;
+                        }
+                        ui.label(egui::RichText::new(format!("Page {} / {}", page + 1, editor.pages.len()))
+                            .size(12.0)
+                            .color(theme.text_secondary));
+                        if ui.add_enabled(page + 1 < editor.pages.len(), egui::Button::new("▶")).clicked() {
+                            page += 1;
+                        }
+                        if page != editor.page {
+                            if let Err(err) = editor.show_page(page) {
+                                editor.message = Some(Err(err));
+                            }
+                        }
+                    });
+                }
+
+                ui.separator();
+                ui.label(egui::RichText::new("Puzzle goal").size(12.0).color(theme.text_secondary));
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("editor_goal")
+                        .selected_text(editor::GOALS[editor.goal])
+                        .show_ui(ui, |ui| {
+                            for (index, goal) in editor::GOALS.iter().enumerate() {
+                                ui.selectable_value(&mut editor.goal, index, *goal);
+                            }
+                        });
+                    // A perfect clear has no count
+                    if editor.goal != 1 {
+                        ui.add(egui::DragValue::new(&mut editor.goal_count).range(1..=20));
+                    }
+                });
+                if ui.button("🧩 Export as puzzle").clicked() {
+                    let result = editor.export_puzzle();
+                    if result.is_ok() {
+                        self.puzzles = PuzzlePack::load();
+                    }
+                    editor.message = Some(result.map(|id| format!("Exported puzzle {id}")));
+                }
+
+                if let Some(message) = &editor.message {
+                    ui.add_space(6.0);
+                    let (text, color) = match message {
+                        Ok(text) => (text, theme.text_primary),
+                        Err(text) => (text, theme.danger),
+                    };
+                    ui.label(egui::RichText::new(text).size(12.0).color(color));
+                }
+
+                ui.add_space(10.0);
+                if ui.button("← Back to Menu").clicked() {
+                    self.state = GameState::Menu;
+                }
+            });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let origin = draw_board::BOARD_OFFSET;
+            let rect = egui::Rect::from_min_size(origin, draw_board::well_size());
+            let response = ui.interact(rect, egui::Id::new("editor_board"), egui::Sense::click_and_drag());
+            draw_board::render_board(ui, theme, &self.editor.board, origin);
+
+            // Paint every cell the pointer passes over while a button is held
+            let (pos, paint, erase) = ctx.input(|i| (i.pointer.interact_pos(), i.pointer.primary_down(), i.pointer.secondary_down()));
+            if let Some(pos) = pos.filter(|_| response.hovered() && (paint || erase)) {
+                let x = ((pos.x - origin.x) / draw_board::CELL_SIZE) as usize;
+                let y = ((pos.y - origin.y) / draw_board::CELL_SIZE) as usize;
+                self.editor.paint(x, y, erase);
+            }
+        });
+
+        if play {
+            self.start_sandbox();
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) && !ctx.wants_keyboard_input() {
+            self.state = GameState::Menu;
+        }
+    }
+
+    fn draw_zen_stats(&self, ui: &mut egui::Ui, theme: &Theme) {
+        let stats = &self.zen_stats;
+        let minutes = (stats.play_time / 60.0) as u64;
+
+        egui::Frame::none()
+            .fill(theme.panel)
+            .rounding(egui::Rounding::same(8.0))
+            .inner_margin(16.0)
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new("ZEN STATISTICS")
+                    .size(12.0)
+                    .color(theme.text_secondary));
+                ui.add_space(6.0);
+                for line in [
+                    format!("Pieces: {}", stats.pieces),
+                    format!("Lines: {}", stats.lines),
+                    format!(
+                        "Singles {} · Doubles {} · Triples {} · Tetrises {}",
+                        stats.clears[0], stats.clears[1], stats.clears[2], stats.clears[3]
+                    ),
+                    format!("Board resets: {}", stats.board_resets),
+                    format!("Time played: {}h {:02}m", minutes / 60, minutes % 60),
+                ] {
+                    ui.label(egui::RichText::new(line)
+                        .size(13.0)
+                        .color(theme.text_primary));
+                }
+            });
+    }
 }
 
 impl eframe::App for TetrisApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if matches!(self.state, GameState::Playing | GameState::Paused) {
+            self.save_zen();
+            if self.can_save() {
+                self.save_and_quit();
+            }
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.request_repaint();
         let theme = self.get_theme();
@@ -152,12 +1073,82 @@ impl eframe::App for TetrisApp {
                         ui.label(egui::RichText::new("Rust Edition")
                             .size(14.0)
                             .color(theme.text_secondary));
-                        ui.add_space(40.0);
+                        ui.add_space(30.0);
+
+                        // Mode selection
+                        egui::ComboBox::from_id_salt("mode_select")
+                            .width(160.0)
+                            .selected_text(self.mode.name())
+                            .show_ui(ui, |ui| {
+                                for mode in GameMode::ALL {
+                                    ui.selectable_value(&mut self.mode, mode, mode.name());
+                                }
+                            });
+                        ui.label(egui::RichText::new(self.mode.description())
+                            .size(11.0)
+                            .color(theme.text_secondary));
+                        ui.add_space(16.0);
 
+                        if SavedGame::exists() {
+                            if ui.add(egui::Button::new(
+                                egui::RichText::new("⏯ Continue").size(16.0)
+                            ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                                self.continue_game();
+                            }
+                            ui.add_space(10.0);
+                        }
+                        let play_label = if self.mode == GameMode::Zen && ZenSession::exists() {
+                            "▶ Resume"
+                        } else {
+                            "▶ Play"
+                        };
                         if ui.add(egui::Button::new(
-                            egui::RichText::new("▶ Play").size(16.0)
+                            egui::RichText::new(play_label).size(16.0)
                         ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
-                            self.reset();
+                            self.daily = None;
+                            if self.mode == GameMode::Puzzle {
+                                self.state = GameState::PuzzleSelect;
+                            } else if self.mode == GameMode::Sandbox {
+                                self.open_editor();
+                            } else {
+                                self.reset();
+                            }
+                        }
+                        ui.add_space(10.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("📅 Daily Challenge").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.calendar_month = daily::today();
+                            self.state = GameState::Daily;
+                        }
+                        ui.add_space(10.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("⚔ Versus").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.start_versus(false);
+                        }
+                        ui.add_space(10.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("🤖 Versus AI").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.start_versus(true);
+                        }
+                        ui.add_space(10.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("🔌 External Bot").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.start_external();
+                        }
+                        if let Some(err) = &self.bot_error {
+                            ui.label(egui::RichText::new(err)
+                                .size(11.0)
+                                .color(theme.danger));
+                        }
+                        ui.add_space(10.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("🎞 Replays").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.open_replays();
                         }
                         ui.add_space(10.0);
                         if ui.add(egui::Button::new(
@@ -173,8 +1164,24 @@ impl eframe::App for TetrisApp {
                         }
                     });
                 });
+
+                // Left alone long enough, the menu gives way to the demo
+                let (dt, active) = ctx.input(|i| (i.stable_dt as f64, !i.events.is_empty()));
+                self.idle_time = if active { 0.0 } else { self.idle_time + dt };
+                if self.state == GameState::Menu && self.idle_time >= demo::IDLE_TIMEOUT {
+                    self.demo = Some(Demo::new());
+                    self.state = GameState::Demo;
+                }
             }
 
+            GameState::Demo => self.update_demo(ctx, &theme),
+
+            GameState::ReplaySelect => self.update_replay_select(ctx, &theme),
+
+            GameState::Replay => self.update_replay(ctx),
+
+            GameState::Editor => self.update_editor(ctx, &theme),
+
             GameState::Settings => {
                 egui::CentralPanel::default().show(ctx, |ui| {
                     ui.vertical_centered(|ui| {
@@ -233,6 +1240,31 @@ impl eframe::App for TetrisApp {
                                 });
                                 ui.add_space(10.0);
 
+                                // Placement hints; runs that show them are marked as assisted
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("Placement Hints:")
+                                        .color(theme.text_primary)
+                                        .size(14.0));
+                                    ui.add_space(8.0);
+                                    ui.checkbox(&mut self.settings.show_hints, "");
+                                });
+                                ui.add_space(10.0);
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("Hint Key:")
+                                        .color(theme.text_primary)
+                                        .size(14.0));
+                                    ui.add_space(8.0);
+                                    let label = if self.rebinding_hint {
+                                        "Press a key…"
+                                    } else {
+                                        self.settings.hint_key.name()
+                                    };
+                                    if ui.button(label).clicked() {
+                                        self.rebinding_hint = true;
+                                    }
+                                });
+                                ui.add_space(10.0);
+
                                 // Colorblind mode toggle
                                 ui.horizontal(|ui| {
                                     ui.label(egui::RichText::new("Colorblind Mode:")
@@ -246,6 +1278,60 @@ impl eframe::App for TetrisApp {
                                             .size(10.0));
                                     }
                                 });
+                                ui.add_space(10.0);
+
+                                // Versus match length
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("Versus Best Of:")
+                                        .color(theme.text_primary)
+                                        .size(14.0));
+                                    ui.add_space(8.0);
+                                    egui::ComboBox::from_id_salt("versus_best_of")
+                                        .selected_text(self.settings.versus_best_of.to_string())
+                                        .show_ui(ui, |ui| {
+                                            for rounds in [1, 3, 5, 7] {
+                                                ui.selectable_value(&mut self.settings.versus_best_of, rounds, rounds.to_string());
+                                            }
+                                        });
+                                });
+                                ui.add_space(10.0);
+
+                                // Same piece sequence for both versus players
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("Versus Same Pieces:")
+                                        .color(theme.text_primary)
+                                        .size(14.0));
+                                    ui.add_space(8.0);
+                                    ui.checkbox(&mut self.settings.versus_same_seed, "");
+                                });
+                                ui.add_space(10.0);
+
+                                // Computer opponent
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("AI Speed (PPS):")
+                                        .color(theme.text_primary)
+                                        .size(14.0));
+                                    ui.add_space(8.0);
+                                    ui.add(egui::Slider::new(&mut self.settings.ai_pps, 0.5..=5.0).step_by(0.1));
+                                });
+                                ui.add_space(10.0);
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("AI Skill:")
+                                        .color(theme.text_primary)
+                                        .size(14.0));
+                                    ui.add_space(8.0);
+                                    ui.add(egui::Slider::new(&mut self.settings.ai_skill, 0.0..=1.0).step_by(0.05));
+                                });
+                                ui.add_space(10.0);
+
+                                // External bot
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("Bot Command:")
+                                        .color(theme.text_primary)
+                                        .size(14.0));
+                                    ui.add_space(8.0);
+                                    ui.text_edit_singleline(&mut self.settings.tbp_command);
+                                });
                             });
 
                         ui.add_space(30.0);
@@ -259,7 +1345,19 @@ impl eframe::App for TetrisApp {
                 });
 
                 ctx.input(|i| {
-                    if i.key_pressed(egui::Key::Escape) {
+                    if self.rebinding_hint {
+                        // The next key pressed becomes the hint key; Escape keeps the old one
+                        let pressed = i.events.iter().find_map(|event| match event {
+                            egui::Event::Key { key, pressed: true, .. } => Some(*key),
+                            _ => None,
+                        });
+                        if let Some(key) = pressed {
+                            if key != egui::Key::Escape {
+                                self.settings.hint_key = key;
+                            }
+                            self.rebinding_hint = false;
+                        }
+                    } else if i.key_pressed(egui::Key::Escape) {
                         self.state = GameState::Menu;
                     }
                 });
@@ -267,83 +1365,75 @@ impl eframe::App for TetrisApp {
 
             GameState::Playing => {
                 // Handle input
+                let view = BoardTransform::for_mode(self.mode);
                 ctx.input(|i| {
-                    if i.key_pressed(egui::Key::ArrowLeft) {
-                        if self.board.can_move(&self.current_piece, -1, 0) {
-                            self.current_piece.x -= 1;
-                        }
-                    }
-                    if i.key_pressed(egui::Key::ArrowRight) {
-                        if self.board.can_move(&self.current_piece, 1, 0) {
-                            self.current_piece.x += 1;
+                    if self.external.is_none() {
+                        for action in self.settings.keys.pressed(i) {
+                            self.game.apply(action.for_view(view));
                         }
                     }
-                    if i.key_pressed(egui::Key::ArrowDown) {
-                        self.soft_drop();
-                    }
-                    if i.key_pressed(egui::Key::Space) {
-                        self.hard_drop();
-                    }
-                    if i.key_pressed(egui::Key::ArrowUp) || i.key_pressed(egui::Key::X) {
-                        let rotated = self.current_piece.rotated_cw();
-                        if let Some(kicked) = Rules::try_wall_kick(&self.board, &rotated) {
-                            self.current_piece = kicked;
-                        }
-                    }
-                    if i.key_pressed(egui::Key::Z) {
-                        let rotated = self.current_piece.rotated_ccw();
-                        if let Some(kicked) = Rules::try_wall_kick(&self.board, &rotated) {
-                            self.current_piece = kicked;
-                        }
-                    }
-                    if i.key_pressed(egui::Key::C) {
-                        self.hold();
+                    if i.key_pressed(self.settings.hint_key) {
+                        self.settings.show_hints = !self.settings.show_hints;
                     }
                     if i.key_pressed(egui::Key::P) || i.key_pressed(egui::Key::Escape) {
-                        self.state = GameState::Paused;
+                        self.pause();
                     }
                     if i.key_pressed(egui::Key::Q) {
-                        self.state = GameState::Menu;
+                        if self.can_save() {
+                            self.save_and_quit();
+                        } else {
+                            self.quit_to_menu();
+                        }
                     }
                 });
 
-                // Gravity tick
                 let dt = ctx.input(|i| i.stable_dt as f64);
-                if self.clock.tick(dt) {
-                    if self.board.can_move(&self.current_piece, 0, 1) {
-                        self.current_piece.y += 1;
-                        self.lock_timer = None;
+                if self.state == GameState::Playing {
+                    // Step in whole frames; a long stall is dropped rather than caught up
+                    self.accumulator = (self.accumulator + dt).min(0.25);
+                    while self.accumulator >= FRAME {
+                        if let Some(player) = &mut self.external {
+                            player.update(&mut self.game, FRAME);
+                        }
+                        self.game.update(FRAME);
+                        self.accumulator -= FRAME;
+                    }
+                    if self.mode == GameMode::Zen {
+                        self.zen_stats.play_time += dt;
                     }
                 }
+                self.handle_game_events();
 
-                // Lock delay (processed every frame, not just on gravity ticks)
-                if !self.board.can_move(&self.current_piece, 0, 1) {
-                    // Piece is on the ground
-                    if let Some(timer) = &mut self.lock_timer {
-                        *timer -= dt;
-                        if *timer <= 0.0 {
-                            self.lock_piece();
-                        }
-                    } else {
-                        self.lock_timer = Some(0.5); // 500ms lock delay
+                // A bot that fails or breaks the rules forfeits
+                if let Some(err) = self.external.as_ref().and_then(|player| player.error.clone()) {
+                    if self.state == GameState::Playing {
+                        self.bot_error = Some(err);
+                        self.game_over();
                     }
-                } else {
-                    // Piece is in the air, reset lock timer
-                    self.lock_timer = None;
                 }
 
                 // Animation updates
                 self.animator.update(dt);
 
+                // Showing a hint marks the run as assisted
+                let hint = if self.settings.show_hints && self.state == GameState::Playing {
+                    self.hint.get(&self.game, &self.settings.ai_weights)
+                } else {
+                    None
+                };
+                if hint.is_some() {
+                    self.game.assisted = true;
+                }
+
                 // Render
                 egui::CentralPanel::default().show(ctx, |ui| {
-                    draw_board::render(ui, &theme, &self.board, &self.current_piece, &self.settings, &self.animator);
+                    draw_board::render(ui, &theme, &self.game, &self.animator, &self.settings, hint, draw_board::BOARD_OFFSET);
                 });
 
                 egui::SidePanel::right("info_panel")
                     .min_width(140.0)
                     .show(ctx, |ui| {
-                        draw_panels::render(ui, &theme, self);
+                        draw_panels::render(ui, &theme, &self.game, self.puzzle.as_ref());
                     });
             }
 
@@ -357,6 +1447,11 @@ impl eframe::App for TetrisApp {
                             .strong());
                         ui.add_space(30.0);
 
+                        if self.mode == GameMode::Zen {
+                            self.draw_zen_stats(ui, &theme);
+                            ui.add_space(20.0);
+                        }
+
                         if ui.add(egui::Button::new(
                             egui::RichText::new("▶ Resume").size(16.0)
                         ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
@@ -369,10 +1464,26 @@ impl eframe::App for TetrisApp {
                             self.state = GameState::Settings;
                         }
                         ui.add_space(10.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("🖌 Edit Position").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.editor.load_game(&self.game);
+                            self.quit_to_menu();
+                            self.open_editor();
+                        }
+                        ui.add_space(10.0);
+                        if self.can_save() {
+                            if ui.add(egui::Button::new(
+                                egui::RichText::new("💾 Save & Quit").size(16.0)
+                            ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                                self.save_and_quit();
+                            }
+                            ui.add_space(10.0);
+                        }
                         if ui.add(egui::Button::new(
                             egui::RichText::new("🏠 Menu").size(16.0)
                         ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
-                            self.state = GameState::Menu;
+                            self.quit_to_menu();
                         }
                     });
                 });
@@ -400,27 +1511,124 @@ impl eframe::App for TetrisApp {
                             .rounding(egui::Rounding::same(8.0))
                             .inner_margin(20.0)
                             .show(ui, |ui| {
-                                ui.label(egui::RichText::new(format!("Score: {}", self.score))
+                                ui.label(egui::RichText::new(format!("Score: {}", self.game.score))
                                     .size(22.0)
                                     .color(theme.text_primary)
                                     .strong());
                                 ui.add_space(5.0);
-                                ui.label(egui::RichText::new(format!("Level: {}", self.level))
+                                ui.label(egui::RichText::new(format!("Level: {}", self.game.level))
                                     .size(16.0)
                                     .color(theme.text_secondary));
-                                ui.label(egui::RichText::new(format!("Lines: {}", self.lines))
+                                ui.label(egui::RichText::new(format!("Lines: {}", self.game.lines))
                                     .size(16.0)
                                     .color(theme.text_secondary));
+                                if self.mode == GameMode::Survival {
+                                    ui.label(egui::RichText::new(format!("Rises survived: {}", self.game.rise_timer.rises()))
+                                        .size(16.0)
+                                        .color(theme.text_secondary));
+                                }
+                                if let Some(challenge) = &self.daily {
+                                    let assisted = self.game.assisted;
+                                    let best = self.daily_history.get(&challenge.date, assisted).map_or(0, |result| result.score);
+                                    let kind = if assisted { "best with hints" } else { "best" };
+                                    ui.add_space(5.0);
+                                    ui.label(egui::RichText::new(format!("Daily {} · {} {}", challenge.date, kind, best))
+                                        .size(14.0)
+                                        .color(theme.text_secondary));
+                                }
+                                if self.game.assisted {
+                                    ui.label(egui::RichText::new("Hints were used")
+                                        .size(14.0)
+                                        .color(theme.text_secondary));
+                                }
+                                if self.game.used_undo {
+                                    ui.label(egui::RichText::new("Undo was used")
+                                        .size(14.0)
+                                        .color(theme.text_secondary));
+                                }
+                                if let Some(player) = &self.external {
+                                    let name = player.name.as_deref().unwrap_or("External bot");
+                                    ui.add_space(5.0);
+                                    ui.label(egui::RichText::new(format!("Played by {name}"))
+                                        .size(14.0)
+                                        .color(theme.text_secondary));
+                                    if let Some(err) = &self.bot_error {
+                                        ui.label(egui::RichText::new(err)
+                                            .size(14.0)
+                                            .color(theme.danger));
+                                    }
+                                }
                             });
                         
                         ui.add_space(30.0);
 
                         if ui.add(egui::Button::new(
                             egui::RichText::new("🔄 Restart").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.restart();
+                        }
+                        ui.add_space(10.0);
+                        if self.mode == GameMode::Sandbox {
+                            if ui.add(egui::Button::new(
+                                egui::RichText::new("🖌 Editor").size(16.0)
+                            ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                                self.open_editor();
+                            }
+                            ui.add_space(10.0);
+                        }
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("🏠 Menu").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.external = None;
+                            self.state = GameState::Menu;
+                        }
+                    });
+                });
+
+                ctx.input(|i| {
+                    if i.key_pressed(egui::Key::R) {
+                        self.restart();
+                    }
+                });
+            }
+
+            GameState::Results => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(60.0);
+                        if self.mode == GameMode::Puzzle {
+                            self.draw_puzzle_results(ui, &theme);
+                        } else {
+                            self.draw_master_results(ui, &theme);
+                        }
+                        ui.add_space(20.0);
+
+                        let retry_label = if self.mode == GameMode::Puzzle { "🔄 Retry" } else { "🔄 Restart" };
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new(retry_label).size(16.0)
                         ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
                             self.reset();
                         }
                         ui.add_space(10.0);
+
+                        if let Some(play) = &self.puzzle {
+                            let next = play.index + 1;
+                            if play.solved == Some(true) && next < self.puzzles.puzzles.len() {
+                                if ui.add(egui::Button::new(
+                                    egui::RichText::new("⏭ Next Puzzle").size(16.0)
+                                ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                                    self.start_puzzle(next);
+                                }
+                                ui.add_space(10.0);
+                            }
+                            if ui.add(egui::Button::new(
+                                egui::RichText::new("🧩 Puzzles").size(16.0)
+                            ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                                self.state = GameState::PuzzleSelect;
+                            }
+                            ui.add_space(10.0);
+                        }
+
                         if ui.add(egui::Button::new(
                             egui::RichText::new("🏠 Menu").size(16.0)
                         ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
@@ -435,6 +1643,235 @@ impl eframe::App for TetrisApp {
                     }
                 });
             }
+
+            GameState::Versus => self.update_versus(ctx, &theme),
+
+            GameState::Daily => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(40.0);
+                        ui.label(egui::RichText::new("📅 DAILY CHALLENGE")
+                            .size(28.0)
+                            .color(theme.text_primary)
+                            .strong());
+                        ui.add_space(10.0);
+
+                        let today = daily::today();
+                        let challenge = daily::challenge_for(today);
+                        ui.label(egui::RichText::new(format!("{} · {}", challenge.date, challenge.mode.name()))
+                            .size(16.0)
+                            .color(theme.text_primary));
+                        ui.label(egui::RichText::new(challenge.mode.description())
+                            .size(11.0)
+                            .color(theme.text_secondary));
+                        if let Some(result) = self.daily_history.get(&challenge.date, false) {
+                            ui.label(egui::RichText::new(format!(
+                                "Best today: {} ({} attempts)",
+                                result.score, result.attempts
+                            ))
+                                .size(13.0)
+                                .color(theme.text_secondary));
+                        }
+                        ui.add_space(10.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("▶ Play").size(16.0)
+                        ).min_size(egui::Vec2::new(120.0, 40.0))).clicked() {
+                            self.start_daily(challenge);
+                        }
+                        ui.add_space(20.0);
+
+                        // Month navigation; the calendar doesn't go past the current month
+                        let month = self.calendar_month;
+                        ui.horizontal(|ui| {
+                            ui.add_space(ui.available_width() / 2.0 - 110.0);
+                            if ui.button("◀").clicked() {
+                                self.calendar_month = month - Months::new(1);
+                            }
+                            ui.label(egui::RichText::new(month.format("%B %Y").to_string())
+                                .size(15.0)
+                                .color(theme.text_primary)
+                                .strong());
+                            let next = month + Months::new(1);
+                            if ui.add_enabled(next.with_day(1) <= Some(today), egui::Button::new("▶")).clicked() {
+                                self.calendar_month = next;
+                            }
+                        });
+                        ui.add_space(8.0);
+
+                        let (offset, days) = daily::month_days(month);
+                        egui::Grid::new("daily_calendar")
+                            .spacing(egui::Vec2::new(6.0, 6.0))
+                            .show(ui, |ui| {
+                                for name in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"] {
+                                    ui.label(egui::RichText::new(name)
+                                        .size(11.0)
+                                        .color(theme.text_secondary));
+                                }
+                                ui.end_row();
+
+                                for _ in 0..offset {
+                                    ui.label("");
+                                }
+                                for (i, day) in days.iter().enumerate() {
+                                    let key = day.format("%Y-%m-%d").to_string();
+                                    let best = self.daily_history.get(&key, false)
+                                        .map_or("—".to_string(), |result| result.score.to_string());
+                                    let fill = if *day == today { theme.border } else { theme.panel };
+                                    egui::Frame::none()
+                                        .fill(fill)
+                                        .rounding(egui::Rounding::same(4.0))
+                                        .inner_margin(4.0)
+                                        .show(ui, |ui| {
+                                            ui.set_width(52.0);
+                                            ui.vertical(|ui| {
+                                                ui.label(egui::RichText::new(day.day().to_string())
+                                                    .size(12.0)
+                                                    .color(theme.text_primary)
+                                                    .strong());
+                                                ui.label(egui::RichText::new(best)
+                                                    .size(10.0)
+                                                    .color(theme.text_secondary));
+                                            });
+                                        });
+                                    if (offset as usize + i) % 7 == 6 {
+                                        ui.end_row();
+                                    }
+                                }
+                            });
+
+                        ui.add_space(20.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("← Back to Menu").size(14.0)
+                        ).min_size(egui::Vec2::new(140.0, 36.0))).clicked() {
+                            self.state = GameState::Menu;
+                        }
+                    });
+                });
+
+                ctx.input(|i| {
+                    if i.key_pressed(egui::Key::Escape) {
+                        self.state = GameState::Menu;
+                    }
+                });
+            }
+
+            GameState::PuzzleSelect => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(40.0);
+                        ui.label(egui::RichText::new("🧩 PUZZLES")
+                            .size(28.0)
+                            .color(theme.text_primary)
+                            .strong());
+                        ui.label(egui::RichText::new(format!(
+                            "{} / {} solved",
+                            self.puzzles.solved_count(),
+                            self.puzzles.puzzles.len()
+                        ))
+                            .size(13.0)
+                            .color(theme.text_secondary));
+                        ui.add_space(20.0);
+
+                        let mut selected = None;
+                        egui::ScrollArea::vertical().max_height(380.0).show(ui, |ui| {
+                            for (index, puzzle) in self.puzzles.puzzles.iter().enumerate() {
+                                egui::Frame::none()
+                                    .fill(theme.panel)
+                                    .rounding(egui::Rounding::same(8.0))
+                                    .inner_margin(10.0)
+                                    .show(ui, |ui| {
+                                        ui.set_width(420.0);
+                                        ui.horizontal(|ui| {
+                                            let mark = if self.puzzles.is_solved(index) { "✔" } else { "○" };
+                                            ui.label(egui::RichText::new(mark)
+                                                .size(18.0)
+                                                .color(theme.text_primary));
+                                            ui.vertical(|ui| {
+                                                ui.label(egui::RichText::new(&puzzle.name)
+                                                    .size(15.0)
+                                                    .color(theme.text_primary)
+                                                    .strong());
+                                                ui.label(egui::RichText::new(format!(
+                                                    "{} · {} pieces",
+                                                    puzzle.goal.description(),
+                                                    puzzle.pieces.chars().filter(|c| !c.is_whitespace()).count()
+                                                ))
+                                                    .size(11.0)
+                                                    .color(theme.text_secondary));
+                                            });
+                                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                                if ui.button("▶ Play").clicked() {
+                                                    selected = Some(index);
+                                                }
+                                            });
+                                        });
+                                    });
+                                ui.add_space(6.0);
+                            }
+                        });
+                        if let Some(index) = selected {
+                            self.start_puzzle(index);
+                        }
+
+                        ui.add_space(20.0);
+                        if ui.add(egui::Button::new(
+                            egui::RichText::new("← Back to Menu").size(14.0)
+                        ).min_size(egui::Vec2::new(140.0, 36.0))).clicked() {
+                            self.state = GameState::Menu;
+                        }
+                    });
+                });
+
+                ctx.input(|i| {
+                    if i.key_pressed(egui::Key::Escape) {
+                        self.state = GameState::Menu;
+                    }
+                });
+            }
         }
     }
 }
+
+/// Draws the replay's timeline: line clears as ticks (taller for more lines, highlighted for
+/// T-spins), the stretch marked for export and the playhead. Returns the frame clicked or dragged to.
+fn draw_timeline(ui: &mut egui::Ui, theme: &Theme, viewer: &ReplayViewer) -> Option<u64> {
+    let (rect, response) = ui.allocate_exact_size(
+        egui::Vec2::new(ui.available_width(), 28.0),
+        egui::Sense::click_and_drag(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, egui::Rounding::same(4.0), theme.background);
+
+    let frames = viewer.frames().max(1) as f32;
+    let x_of = |frame: u64| rect.left() + rect.width() * frame as f32 / frames;
+    for marker in &viewer.markers {
+        let height = rect.height() * (0.3 + 0.175 * marker.cleared.min(4) as f32);
+        let color = if marker.t_spin == TSpin::None { theme.text_secondary } else { theme.glow_color };
+        let x = x_of(marker.frame);
+        painter.line_segment(
+            [egui::Pos2::new(x, rect.bottom()), egui::Pos2::new(x, rect.bottom() - height)],
+            egui::Stroke::new(2.0, color),
+        );
+    }
+    if viewer.segment_start > 0 {
+        let (from, to) = (x_of(viewer.segment_start), x_of(viewer.game.frame));
+        let band = egui::Rect::from_x_y_ranges(from.min(to)..=from.max(to), rect.y_range());
+        painter.rect_filled(band, egui::Rounding::ZERO, theme.glow_color.gamma_multiply(0.2));
+        painter.line_segment([egui::Pos2::new(from, rect.top()), egui::Pos2::new(from, rect.bottom())], egui::Stroke::new(2.0, theme.glow_color));
+    }
+    let x = x_of(viewer.game.frame);
+    painter.line_segment([egui::Pos2::new(x, rect.top()), egui::Pos2::new(x, rect.bottom())], egui::Stroke::new(2.0, theme.text_primary));
+
+    if response.clicked() || response.dragged() {
+        let pointer = response.interact_pointer_pos()?;
+        let share = ((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+        return Some((share * frames) as u64);
+    }
+    None
+}
+
+/// Formats seconds as m:ss.cc
+pub fn format_time(seconds: f64) -> String {
+    let centis = (seconds * 100.0) as u64;
+    format!("{}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
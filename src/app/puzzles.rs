@@ -0,0 +1,80 @@
+use std::collections::BTreeSet;
+use std::fs;
+
+use crate::core::{Puzzle, PuzzleRun};
+use super::storage;
+
+const BUNDLED_PUZZLES: &str = include_str!("../../assets/puzzles.json");
+const PROGRESS_FILE: &str = "puzzle_progress.json";
+/// Subdirectory of the data directory scanned for extra puzzle files
+pub const USER_PUZZLE_DIR: &str = "puzzles";
+
+/// Bundled puzzles plus any found in the user's puzzle directory, with completion tracking
+pub struct PuzzlePack {
+    pub puzzles: Vec<Puzzle>,
+    solved: BTreeSet<String>,
+}
+
+impl PuzzlePack {
+    pub fn load() -> Self {
+        let mut puzzles: Vec<Puzzle> =
+            serde_json::from_str(BUNDLED_PUZZLES).expect("bundled puzzles are valid JSON");
+
+        // User puzzles: one puzzle per file
+        if let Ok(entries) = fs::read_dir(storage::data_dir().join(USER_PUZZLE_DIR)) {
+            let mut paths: Vec<_> = entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            paths.sort();
+
+            for path in paths {
+                let parsed = fs::read_to_string(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| serde_json::from_str::<Puzzle>(&text).map_err(|err| err.to_string()));
+                match parsed {
+                    Ok(puzzle) => puzzles.push(puzzle),
+                    Err(err) => eprintln!("Skipping puzzle {}: {err}", path.display()),
+                }
+            }
+        }
+
+        puzzles.retain(|puzzle| match puzzle.validate() {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!("Skipping invalid puzzle: {err}");
+                false
+            }
+        });
+
+        Self {
+            puzzles,
+            solved: storage::load_json(PROGRESS_FILE).unwrap_or_default(),
+        }
+    }
+
+    pub fn is_solved(&self, index: usize) -> bool {
+        self.solved.contains(&self.puzzles[index].id)
+    }
+
+    pub fn solved_count(&self) -> usize {
+        self.puzzles.iter().filter(|p| self.solved.contains(&p.id)).count()
+    }
+
+    pub fn mark_solved(&mut self, index: usize) {
+        if self.solved.insert(self.puzzles[index].id.clone()) {
+            if let Err(err) = storage::save_json(PROGRESS_FILE, &self.solved) {
+                eprintln!("Failed to save puzzle progress: {err}");
+            }
+        }
+    }
+}
+
+/// The puzzle currently being played
+pub struct PuzzlePlay {
+    pub index: usize,
+    pub puzzle: Puzzle,
+    pub run: PuzzleRun,
+    /// Set when the sequence runs out or the stack tops out
+    pub solved: Option<bool>,
+}
@@ -0,0 +1,370 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use crate::core::{Action, Game, GameMode};
+use crate::render::theme::ThemeType;
+use super::settings::Settings;
+use super::storage;
+
+/// Subdirectory of the data directory holding replays
+pub const REPLAY_DIR: &str = "replays";
+pub const REPLAY_EXTENSION: &str = "replay";
+
+const MAGIC: &[u8; 4] = b"TRPL";
+/// Bumped whenever the layout changes; older versions are still read where possible
+pub const REPLAY_VERSION: u16 = 3;
+
+/// How the game was set up, besides its seeds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayStart {
+    /// An ordinary game from the menu
+    Fresh,
+    /// A daily challenge, by date
+    Daily(String),
+    /// A puzzle from the pack, by id
+    Puzzle(String),
+    /// A board editor position, by its share code
+    Layout(String),
+}
+
+/// The settings in effect when the game was played, so it can be watched the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaySettings {
+    pub show_ghost: bool,
+    pub show_grid: bool,
+    pub colorblind_mode: bool,
+    pub theme: ThemeType,
+}
+
+impl ReplaySettings {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            show_ghost: settings.show_ghost,
+            show_grid: settings.show_grid,
+            colorblind_mode: settings.colorblind_mode,
+            theme: settings.theme,
+        }
+    }
+}
+
+/// Everything needed to play a game again: its rules, seeds and every input with the frame
+/// it arrived on. The engine is stepped at a fixed rate, so this reproduces the game exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub mode: GameMode,
+    pub seed: u64,
+    pub garbage_seed: u64,
+    pub start: ReplayStart,
+    pub settings: ReplaySettings,
+    /// Unix time the game ended
+    pub recorded_at: i64,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub frames: u64,
+    pub inputs: Vec<(u64, Action)>,
+}
+
+/// Seeds and setup of a game being recorded, kept until it ends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub garbage_seed: u64,
+    pub start: ReplayStart,
+}
+
+impl Recording {
+    pub fn finish(self, game: &Game, settings: &Settings) -> Replay {
+        Replay {
+            mode: game.mode,
+            seed: self.seed,
+            garbage_seed: self.garbage_seed,
+            start: self.start,
+            settings: ReplaySettings::from_settings(settings),
+            recorded_at: Local::now().timestamp(),
+            score: game.score,
+            lines: game.lines,
+            level: game.level,
+            frames: game.frame,
+            inputs: game.inputs.clone(),
+        }
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("bad replay: {reason}"))
+}
+
+fn mode_index(mode: GameMode) -> u8 {
+    GameMode::ALL.iter().position(|&m| m == mode).unwrap_or(0) as u8
+}
+
+const THEMES: [ThemeType; 3] = [ThemeType::Neon, ThemeType::Classic, ThemeType::Minimal];
+
+/// Little-endian writer for the replay layout
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// LEB128: small numbers, like the gaps between inputs, take a single byte
+    fn varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.0.push(byte);
+                return;
+            }
+            self.0.push(byte | 0x80);
+        }
+    }
+
+    fn string(&mut self, value: &str) {
+        self.varint(value.len() as u64);
+        self.0.extend_from_slice(value.as_bytes());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let (bytes, rest) = self.0.split_first_chunk::<N>().ok_or_else(|| invalid("truncated"))?;
+        self.0 = rest;
+        Ok(*bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.varint()? as usize;
+        if len > self.0.len() {
+            return Err(invalid("truncated"));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("text is not UTF-8"))
+    }
+}
+
+impl Replay {
+    /// Layout (little-endian): magic, version, header, result, then the inputs as
+    /// (frame gap varint, action byte) pairs
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Writer(Vec::new());
+        out.0.extend_from_slice(MAGIC);
+        out.u16(REPLAY_VERSION);
+
+        out.u8(mode_index(self.mode));
+        out.u64(self.seed);
+        out.u64(self.garbage_seed);
+        match &self.start {
+            ReplayStart::Fresh => out.u8(0),
+            ReplayStart::Daily(date) => {
+                out.u8(1);
+                out.string(date);
+            }
+            ReplayStart::Puzzle(id) => {
+                out.u8(2);
+                out.string(id);
+            }
+            ReplayStart::Layout(code) => {
+                out.u8(3);
+                out.string(code);
+            }
+        }
+        let settings = &self.settings;
+        let flags = settings.show_ghost as u8 | (settings.show_grid as u8) << 1 | (settings.colorblind_mode as u8) << 2;
+        out.u8(flags);
+        out.u8(THEMES.iter().position(|&theme| theme == settings.theme).unwrap_or(0) as u8);
+        out.u64(self.recorded_at as u64);
+
+        out.u32(self.score);
+        out.u32(self.lines);
+        out.u32(self.level);
+        out.varint(self.frames);
+
+        out.varint(self.inputs.len() as u64);
+        let mut last_frame = 0;
+        for &(frame, action) in &self.inputs {
+            out.varint(frame - last_frame);
+            out.u8(Action::ALL.iter().position(|&a| a == action).unwrap_or(0) as u8);
+            last_frame = frame;
+        }
+        out.0
+    }
+
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut input = Reader(bytes);
+        if &input.take::<4>()? != MAGIC {
+            return Err(invalid("not a replay file"));
+        }
+        let version = input.u16()?;
+        if version == 0 || version > REPLAY_VERSION {
+            return Err(invalid(&format!("unsupported version {version}")));
+        }
+
+        let mode = *GameMode::ALL.get(input.u8()? as usize).ok_or_else(|| invalid("unknown mode"))?;
+        let seed = input.u64()?;
+        let garbage_seed = input.u64()?;
+        let start = match input.u8()? {
+            0 => ReplayStart::Fresh,
+            1 => ReplayStart::Daily(input.string()?),
+            2 => ReplayStart::Puzzle(input.string()?),
+            3 => ReplayStart::Layout(input.string()?),
+            _ => return Err(invalid("unknown start")),
+        };
+        let flags = input.u8()?;
+        let theme = *THEMES.get(input.u8()? as usize).ok_or_else(|| invalid("unknown theme"))?;
+        let settings = ReplaySettings {
+            show_ghost: flags & 1 != 0,
+            show_grid: flags & 2 != 0,
+            colorblind_mode: flags & 4 != 0,
+            theme,
+        };
+        let recorded_at = input.u64()? as i64;
+
+        let score = input.u32()?;
+        let lines = input.u32()?;
+        let level = input.u32()?;
+        let frames = input.varint()?;
+
+        let count = input.varint()? as usize;
+        // Each input takes at least two bytes; don't trust a count the file can't hold
+        if count > input.0.len() / 2 {
+            return Err(invalid("truncated"));
+        }
+        let mut inputs = Vec::with_capacity(count);
+        let mut frame = 0u64;
+        for _ in 0..count {
+            frame = frame.checked_add(input.varint()?).ok_or_else(|| invalid("frame overflow"))?;
+            let action = *Action::ALL.get(input.u8()? as usize).ok_or_else(|| invalid("unknown action"))?;
+            inputs.push((frame, action));
+        }
+
+        Ok(Self {
+            mode,
+            seed,
+            garbage_seed,
+            start,
+            settings,
+            recorded_at,
+            score,
+            lines,
+            level,
+            frames,
+            inputs,
+        })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    /// Writes the replay to the replays directory, named after when and what was played
+    pub fn save(&self) -> io::Result<PathBuf> {
+        let dir = storage::data_dir().join(REPLAY_DIR);
+        fs::create_dir_all(&dir)?;
+        let when = DateTime::from_timestamp(self.recorded_at, 0)
+            .map(|time| time.with_timezone(&Local).format("%Y-%m-%d_%H-%M-%S").to_string())
+            .unwrap_or_default();
+        let mode = self.mode.name().to_lowercase();
+        let path = dir.join(format!("{when}_{mode}.{REPLAY_EXTENSION}"));
+        fs::write(&path, self.encode())?;
+        Ok(path)
+    }
+}
+
+/// Every readable replay in the replays directory, newest first
+pub fn list() -> Vec<(PathBuf, Replay)> {
+    let Ok(entries) = fs::read_dir(storage::data_dir().join(REPLAY_DIR)) else {
+        return Vec::new();
+    };
+    let mut replays: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == REPLAY_EXTENSION))
+        .filter_map(|path| Replay::load(&path).ok().map(|replay| (path, replay)))
+        .collect();
+    replays.sort_by_key(|(_, replay)| std::cmp::Reverse(replay.recorded_at));
+    replays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoded_replay_decodes_unchanged() {
+        let replay = Replay {
+            mode: GameMode::Survival,
+            seed: 0x0123_4567_89ab_cdef,
+            garbage_seed: u64::MAX,
+            start: ReplayStart::Daily("2026-10-19".to_string()),
+            settings: ReplaySettings {
+                show_ghost: true,
+                show_grid: false,
+                colorblind_mode: true,
+                theme: ThemeType::Minimal,
+            },
+            recorded_at: 1_792_382_400,
+            score: 123_456,
+            lines: 78,
+            level: 7,
+            frames: 90_000,
+            inputs: Action::ALL.iter().enumerate().map(|(i, &action)| (i as u64 * 37, action)).collect(),
+        };
+        let bytes = replay.encode();
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(Replay::decode(&bytes).unwrap(), replay);
+
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert!(Replay::decode(&corrupt).is_err());
+        assert!(Replay::decode(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use crate::core::{Game, GameEvent, GameMode, BagRandomizer, Layout, SeededRng, TSpin, fumen::{self, Page, Placement}, game::FRAME};
+use crate::render::Animator;
+use super::puzzles::PuzzlePack;
+use super::replay::{Replay, ReplayStart};
+use super::settings::Settings;
+
+/// Frames between the snapshots seeking restarts from
+const SNAPSHOT_INTERVAL: u64 = 300;
+/// Playback speeds offered, slowest first
+pub const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// Something worth marking on the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub frame: u64,
+    pub cleared: u32,
+    pub t_spin: TSpin,
+}
+
+/// Plays a replay back by feeding its inputs to a fresh engine. The whole game is simulated
+/// once up front, keeping a snapshot every few seconds, so seeking only re-simulates from the
+/// nearest snapshot.
+pub struct ReplayViewer {
+    pub replay: Replay,
+    pub game: Game,
+    pub animator: Animator,
+    /// Settings the game was recorded with, for the renderer
+    pub settings: Settings,
+    pub playing: bool,
+    pub speed: f64,
+    pub markers: Vec<Marker>,
+    /// Frame a fumen export starts from; it runs to the playhead
+    pub segment_start: u64,
+    /// Result of the last export, shown by the controls
+    pub notice: Option<String>,
+    snapshots: Vec<Game>,
+    /// Index of the next input to apply
+    next_input: usize,
+    accumulator: f64,
+}
+
+impl ReplayViewer {
+    pub fn new(replay: Replay, puzzles: &PuzzlePack, settings: &Settings) -> Result<Self, String> {
+        let start = Self::initial_game(&replay, puzzles)?;
+        let mut settings = settings.clone();
+        settings.show_ghost = replay.settings.show_ghost;
+        settings.show_grid = replay.settings.show_grid;
+        settings.colorblind_mode = replay.settings.colorblind_mode;
+        settings.theme = replay.settings.theme;
+        // Hints were drawn for the player, not recorded
+        settings.show_hints = false;
+
+        let mut viewer = Self {
+            game: start.clone(),
+            replay,
+            animator: Animator::new(),
+            settings,
+            playing: true,
+            speed: 1.0,
+            markers: Vec::new(),
+            segment_start: 0,
+            notice: None,
+            snapshots: Vec::new(),
+            next_input: 0,
+            accumulator: 0.0,
+        };
+
+        // Simulate to the end once for the snapshots and the timeline
+        while viewer.game.frame < viewer.replay.frames && viewer.game.end.is_none() {
+            if viewer.game.frame.is_multiple_of(SNAPSHOT_INTERVAL) {
+                viewer.snapshots.push(viewer.game.clone());
+            }
+            for event in viewer.step() {
+                if let GameEvent::Locked { cleared, t_spin } = event {
+                    if cleared > 0 || t_spin != TSpin::None {
+                        viewer.markers.push(Marker { frame: viewer.game.frame, cleared, t_spin });
+                    }
+                }
+            }
+        }
+        viewer.game = start;
+        viewer.next_input = 0;
+        Ok(viewer)
+    }
+
+    /// The game as it stood on frame 0
+    fn initial_game(replay: &Replay, puzzles: &PuzzlePack) -> Result<Game, String> {
+        if let ReplayStart::Puzzle(id) = &replay.start {
+            let puzzle = puzzles
+                .puzzles
+                .iter()
+                .find(|puzzle| &puzzle.id == id)
+                .ok_or_else(|| format!("puzzle '{id}' is no longer in the pack"))?;
+            let board = puzzle.build_board()?;
+            let sequence = puzzle.sequence()?;
+            return Ok(Game::with_sequence(GameMode::Puzzle, board, sequence));
+        }
+        let rng = BagRandomizer::with_seed(replay.seed);
+        let garbage_rng = SeededRng::new(replay.garbage_seed);
+        if let ReplayStart::Layout(code) = &replay.start {
+            return Game::from_layout(replay.mode, &Layout::from_code(code)?, rng, garbage_rng);
+        }
+        Ok(Game::new(replay.mode, rng, garbage_rng))
+    }
+
+    /// Length of the replay in frames
+    pub fn frames(&self) -> u64 {
+        self.replay.frames
+    }
+
+    pub fn finished(&self) -> bool {
+        self.game.frame >= self.replay.frames || self.game.end.is_some()
+    }
+
+    /// Applies the inputs up to and including `frame`
+    fn apply_inputs(&mut self, frame: u64) {
+        while let Some(&(at, action)) = self.replay.inputs.get(self.next_input) {
+            if at > frame {
+                break;
+            }
+            self.game.apply(action);
+            self.next_input += 1;
+        }
+    }
+
+    /// Applies this frame's inputs and advances one frame. Inputs made after the last step
+    /// (say, just before quitting) are applied on reaching the end.
+    fn step(&mut self) -> Vec<GameEvent> {
+        self.apply_inputs(self.game.frame);
+        self.game.update(FRAME);
+        if self.game.frame >= self.replay.frames {
+            self.apply_inputs(u64::MAX);
+        }
+        self.game.take_events()
+    }
+
+    /// Advances one frame with the usual animations
+    pub fn step_frame(&mut self) {
+        if self.finished() {
+            return;
+        }
+        for event in self.step() {
+            if let GameEvent::Locked { cleared, .. } = event {
+                self.animator.trigger_lock_pop();
+                if cleared > 0 {
+                    self.animator.trigger_line_clear();
+                    if self.game.mode.hides_stack() {
+                        self.animator.trigger_stack_outline();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Jumps to `frame`, re-simulating from the nearest snapshot before it
+    pub fn seek(&mut self, frame: u64) {
+        let frame = frame.min(self.replay.frames);
+        let index = (frame / SNAPSHOT_INTERVAL) as usize;
+        let Some(snapshot) = self.snapshots.get(index.min(self.snapshots.len().saturating_sub(1))) else {
+            return;
+        };
+        // Stepping forward from where we are is cheaper when the target is close ahead
+        if frame < self.game.frame || snapshot.frame > self.game.frame {
+            self.game = snapshot.clone();
+            self.next_input = self.replay.inputs.partition_point(|&(input, _)| input < self.game.frame);
+        }
+        while self.game.frame < frame && !self.finished() {
+            self.step();
+        }
+        self.accumulator = 0.0;
+    }
+
+    /// The game between two frames as a fumen diagram: a page for each piece placed, on the
+    /// board it was placed on, then the position reached. Playback is left where it was.
+    pub fn fumen(&mut self, from: u64, to: u64) -> String {
+        let resume = self.game.frame;
+        self.seek(from);
+        let mut pages: Vec<Page> = Vec::new();
+        while self.game.frame < to && !self.finished() {
+            let board = self.game.board.clone();
+            let mut placed_this_frame = false;
+            for event in self.step() {
+                if let GameEvent::Placed { shape, blocks } = event {
+                    // A second lock in the same frame starts from where the first one left off
+                    let mut page = match pages.last() {
+                        Some(last) if placed_this_frame => last.next(),
+                        _ => Page::new(&board),
+                    };
+                    page.piece = Placement::from_blocks(shape, &blocks);
+                    pages.push(page);
+                    placed_this_frame = true;
+                }
+            }
+        }
+        pages.push(Page::new(&self.game.board));
+        self.seek(resume);
+        fumen::encode(&pages)
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        if self.playing {
+            self.accumulator = (self.accumulator + dt * self.speed).min(0.25 * self.speed);
+            while self.accumulator >= FRAME && !self.finished() {
+                self.step_frame();
+                self.accumulator -= FRAME;
+            }
+            if self.finished() {
+                self.playing = false;
+            }
+        }
+        self.animator.update(dt);
+    }
+}
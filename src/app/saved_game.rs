@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use crate::core::{DailyChallenge, Game};
+use super::replay::Recording;
+use super::storage;
+
+const SAVE_FILE: &str = "saved_game.json";
+
+/// A game put aside with Save & Quit: the complete engine state plus what the app tracks
+/// alongside it, so Continue picks up on the exact frame it left
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub game: Game,
+    pub daily: Option<DailyChallenge>,
+    /// The replay keeps recording across the break
+    pub recording: Option<Recording>,
+    /// Frame time not yet stepped
+    pub accumulator: f64,
+}
+
+impl SavedGame {
+    pub fn load() -> Option<Self> {
+        storage::load_json(SAVE_FILE)
+    }
+
+    pub fn exists() -> bool {
+        storage::exists(SAVE_FILE)
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = storage::save_json(SAVE_FILE, self) {
+            eprintln!("Failed to save game: {err}");
+        }
+    }
+
+    /// Removes the save once it is resumed, so a game can only be continued once
+    pub fn clear() {
+        if let Err(err) = storage::remove(SAVE_FILE) {
+            eprintln!("Failed to remove saved game: {err}");
+        }
+    }
+}
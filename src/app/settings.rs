@@ -1,11 +1,36 @@
+use eframe::egui::Key;
+use crate::core::ai::Weights;
 use crate::render::theme::ThemeType;
+use super::input::KeyBindings;
+
+/// Evaluation weights written by `tetris-sim tune`, in the data directory
+pub const AI_WEIGHTS_FILE: &str = "ai_weights.json";
 
 #[derive(Debug, Clone)]
 pub struct Settings {
     pub show_ghost: bool,
     pub show_grid: bool,
+    /// Outline the AI's suggested placement for the current piece
+    pub show_hints: bool,
+    /// Toggles the hint overlay while playing
+    pub hint_key: Key,
     pub colorblind_mode: bool,
     pub theme: ThemeType,
+    pub keys: KeyBindings,
+    /// Key bindings of the two versus players
+    pub versus_keys: [KeyBindings; 2],
+    /// Rounds in a versus match; the first to win a majority takes it
+    pub versus_best_of: u32,
+    /// Whether both versus players get the same piece sequence
+    pub versus_same_seed: bool,
+    /// Speed of the computer opponent, in pieces per second
+    pub ai_pps: f64,
+    /// Skill of the computer opponent, from 0.0 (often misplaces) to 1.0 (always picks its best move)
+    pub ai_skill: f64,
+    /// Board evaluation of the computer opponent and the hints
+    pub ai_weights: Weights,
+    /// Command line starting the external TBP bot
+    pub tbp_command: String,
 }
 
 impl Default for Settings {
@@ -13,8 +38,27 @@ impl Default for Settings {
         Self {
             show_ghost: true,
             show_grid: true,
+            show_hints: false,
+            hint_key: Key::H,
             colorblind_mode: false,
             theme: ThemeType::Neon,
+            keys: KeyBindings::default(),
+            versus_keys: [KeyBindings::player_one(), KeyBindings::player_two()],
+            versus_best_of: 3,
+            versus_same_seed: true,
+            ai_pps: 1.5,
+            ai_skill: 0.8,
+            ai_weights: Weights::default(),
+            tbp_command: default_bot_command(),
         }
     }
 }
+
+/// The mock bot built alongside the game
+fn default_bot_command() -> String {
+    let name = format!("tbp-mock-bot{}", std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(&name)))
+        .map_or(name, |path| path.display().to_string())
+}
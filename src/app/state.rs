@@ -1,8 +1,19 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameState {
     Menu,
+    /// Attract mode shown after the menu sits idle
+    Demo,
     Settings,
+    PuzzleSelect,
+    Daily,
+    Versus,
     Playing,
     Paused,
     GameOver,
+    Results,
+    ReplaySelect,
+    /// Watching a recorded game
+    Replay,
+    /// Painting a board to play from
+    Editor,
 }
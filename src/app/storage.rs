@@ -0,0 +1,42 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+const APP_DIR: &str = "tetris_rust";
+
+/// Directory holding saves, records and other persistent data
+pub fn data_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join(APP_DIR)
+}
+
+/// Reads `name` from the data directory, returning None if it is missing or unreadable
+pub fn load_json<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let text = fs::read_to_string(data_dir().join(name)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+pub fn exists(name: &str) -> bool {
+    data_dir().join(name).is_file()
+}
+
+pub fn save_json<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+    let text = serde_json::to_string_pretty(value).map_err(io::Error::other)?;
+    fs::write(dir.join(name), text)
+}
+
+/// Deletes `name` from the data directory; a file that is already gone is fine
+pub fn remove(name: &str) -> io::Result<()> {
+    match fs::remove_file(data_dir().join(name)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,134 @@
+use rand::Rng;
+use crate::core::{Action, Bot, Game, GameEvent, GameMode, BagRandomizer, SeededRng};
+use crate::render::Animator;
+
+/// How a versus round ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundResult {
+    Winner(usize),
+    /// Both players topped out on the same frame; nobody scores
+    Draw,
+}
+
+/// Two-player match: two games side by side exchanging garbage, best of N rounds.
+/// The second player is either a person on the same keyboard or a bot.
+pub struct VersusMatch {
+    pub games: [Game; 2],
+    pub animators: [Animator; 2],
+    pub wins: [u32; 2],
+    pub best_of: u32,
+    pub same_seed: bool,
+    /// Set while the round-over screen is up
+    pub round_result: Option<RoundResult>,
+    /// Computer opponent playing the second game
+    pub bot: Option<Bot>,
+}
+
+impl VersusMatch {
+    pub fn new(best_of: u32, same_seed: bool, bot: Option<Bot>) -> Self {
+        let (first, second) = Self::round_games(same_seed);
+        Self {
+            games: [first, second],
+            animators: [Animator::new(), Animator::new()],
+            wins: [0, 0],
+            best_of: best_of.max(1),
+            same_seed,
+            round_result: None,
+            bot,
+        }
+    }
+
+    fn round_games(same_seed: bool) -> (Game, Game) {
+        let (first, second) = if same_seed {
+            let seed = rand::thread_rng().gen();
+            (BagRandomizer::with_seed(seed), BagRandomizer::with_seed(seed))
+        } else {
+            (BagRandomizer::new(), BagRandomizer::new())
+        };
+        (
+            Game::new(GameMode::Marathon, first, SeededRng::from_entropy()),
+            Game::new(GameMode::Marathon, second, SeededRng::from_entropy()),
+        )
+    }
+
+    /// Rounds a player must win to take the match
+    pub fn wins_needed(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    pub fn player_name(&self, player: usize) -> String {
+        if player == 1 && self.bot.is_some() {
+            "CPU".to_string()
+        } else {
+            format!("PLAYER {}", player + 1)
+        }
+    }
+
+    pub fn match_winner(&self) -> Option<usize> {
+        (0..2).find(|&player| self.wins[player] >= self.wins_needed())
+    }
+
+    pub fn next_round(&mut self) {
+        let (first, second) = Self::round_games(self.same_seed);
+        self.games = [first, second];
+        self.animators = [Animator::new(), Animator::new()];
+        self.round_result = None;
+    }
+
+    /// Applies a human player's input; the bot's game ignores the keyboard
+    pub fn apply(&mut self, player: usize, action: Action) {
+        if self.bot.is_some() && player == 1 {
+            return;
+        }
+        if self.round_result.is_none() {
+            self.games[player].apply(action);
+        }
+    }
+
+    /// Steps both games, sends attacks across and decides the round once someone tops out
+    pub fn update(&mut self, dt: f64) {
+        for animator in &mut self.animators {
+            animator.update(dt);
+        }
+        if self.round_result.is_some() {
+            return;
+        }
+
+        if let Some(bot) = &mut self.bot {
+            bot.update(&mut self.games[1], dt);
+        }
+        for game in &mut self.games {
+            game.update(dt);
+        }
+        self.exchange();
+
+        let over = [self.games[0].end.is_some(), self.games[1].end.is_some()];
+        self.round_result = match over {
+            [true, true] => Some(RoundResult::Draw),
+            [true, false] => Some(RoundResult::Winner(1)),
+            [false, true] => Some(RoundResult::Winner(0)),
+            [false, false] => None,
+        };
+        if let Some(RoundResult::Winner(player)) = self.round_result {
+            self.wins[player] += 1;
+        }
+    }
+
+    /// Routes each player's attack to the other and triggers their animations
+    fn exchange(&mut self) {
+        for player in 0..2 {
+            for event in self.games[player].take_events() {
+                match event {
+                    GameEvent::Attack(lines) => self.games[1 - player].receive_garbage(lines),
+                    GameEvent::Locked { cleared, .. } => {
+                        self.animators[player].trigger_lock_pop();
+                        if cleared > 0 {
+                            self.animators[player].trigger_line_clear();
+                        }
+                    }
+                    GameEvent::Placed { .. } | GameEvent::BoardReset => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use crate::core::{Board, tetromino::TetrominoShape};
+use super::storage;
+
+const SESSION_FILE: &str = "zen_session.json";
+
+/// Lifetime statistics of the Zen session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ZenStats {
+    pub pieces: u64,
+    pub lines: u64,
+    /// Singles, doubles, triples and tetrises
+    pub clears: [u64; 4],
+    pub board_resets: u64,
+    pub play_time: f64,
+}
+
+impl ZenStats {
+    pub fn record_lock(&mut self, cleared: u32) {
+        self.pieces += 1;
+        self.lines += cleared as u64;
+        if (1..=4).contains(&cleared) {
+            self.clears[cleared as usize - 1] += 1;
+        }
+    }
+}
+
+/// Everything needed to resume Zen mode on the next launch
+#[derive(Serialize, Deserialize)]
+pub struct ZenSession {
+    pub board: Board,
+    pub current: TetrominoShape,
+    pub next: TetrominoShape,
+    pub hold: Option<TetrominoShape>,
+    pub score: u32,
+    pub level: u32,
+    pub lines: u32,
+    pub stats: ZenStats,
+}
+
+impl ZenSession {
+    pub fn load() -> Option<Self> {
+        storage::load_json(SESSION_FILE)
+    }
+
+    pub fn exists() -> bool {
+        storage::exists(SESSION_FILE)
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = storage::save_json(SESSION_FILE, self) {
+            eprintln!("Failed to save Zen session: {err}");
+        }
+    }
+}
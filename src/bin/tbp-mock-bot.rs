@@ -0,0 +1,129 @@
+//! Minimal TBP bot for trying the external bot mode without a real bot. It plays the game's
+//! own placement heuristic.
+//!
+//! Flags:
+//!   --delay-ms <n>   wait before each suggestion (to exercise the timeout)
+//!   --illegal        suggest a move no piece can reach (to exercise illegal-move handling)
+
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+
+use tetris_rust::core::{Board, Piece, ai::{self, Weights}, tetromino::TetrominoShape};
+use tetris_rust::tbp::messages::from_tbp_board;
+use tetris_rust::tbp::{BotMessage, FrontendMessage, Move, Orientation, PieceLocation, Spin};
+
+/// Moves offered per suggestion, best first
+const SUGGESTIONS: usize = 3;
+
+struct State {
+    board: Board,
+    queue: Vec<TetrominoShape>,
+    hold: Option<TetrominoShape>,
+}
+
+impl State {
+    fn suggest(&self) -> Vec<Move> {
+        let Some(&current) = self.queue.first() else {
+            return Vec::new();
+        };
+        let alternative = self.hold.or(self.queue.get(1).copied()).map(Piece::new);
+        ai::ranked_placements(&self.board, &Piece::new(current), alternative.as_ref(), &Weights::default())
+            .iter()
+            .filter_map(|placement| PieceLocation::from_piece(&placement.piece))
+            .take(SUGGESTIONS)
+            .map(|location| Move { location, spin: Spin::None })
+            .collect()
+    }
+
+    fn play(&mut self, mv: &Move) {
+        let location = mv.location;
+        if self.queue.first() != Some(&location.kind) {
+            // A held piece: the current one goes to hold, and the next comes in if hold was empty
+            let current = self.queue.remove(0);
+            if self.hold.take().is_none() {
+                self.queue.remove(0);
+            }
+            self.hold = Some(current);
+        } else {
+            self.queue.remove(0);
+        }
+        for (x, y) in location.cells() {
+            if x >= 0 && y >= 0 {
+                self.board.grid[y as usize][x as usize] = location.kind.color_id();
+            }
+        }
+        self.board.clear_lines();
+    }
+}
+
+fn reply(message: &BotMessage) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", serde_json::to_string(message).map_err(io::Error::other)?)?;
+    stdout.flush()
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let delay = args
+        .iter()
+        .position(|arg| arg == "--delay-ms")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|ms| ms.parse().ok())
+        .map(Duration::from_millis);
+    let illegal = args.iter().any(|arg| arg == "--illegal");
+
+    reply(&BotMessage::Info {
+        name: "Mock Bot".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        author: "tetris_rust".to_string(),
+        features: Vec::new(),
+    })?;
+
+    let mut state: Option<State> = None;
+    for line in io::stdin().lock().lines() {
+        let message: FrontendMessage = match serde_json::from_str(&line?) {
+            Ok(message) => message,
+            Err(err) => {
+                eprintln!("mock bot: ignoring message: {err}");
+                continue;
+            }
+        };
+        match message {
+            FrontendMessage::Rules => reply(&BotMessage::Ready)?,
+            FrontendMessage::Start(start) => {
+                state = Some(State {
+                    board: from_tbp_board(&start.board),
+                    queue: start.queue,
+                    hold: start.hold,
+                });
+            }
+            FrontendMessage::Stop => state = None,
+            FrontendMessage::Suggest => {
+                if let Some(delay) = delay {
+                    thread::sleep(delay);
+                }
+                let moves = if illegal {
+                    // Buried under the floor
+                    let location = PieceLocation { kind: TetrominoShape::O, orientation: Orientation::North, x: 4, y: -5 };
+                    vec![Move { location, spin: Spin::None }]
+                } else {
+                    state.as_ref().map(State::suggest).unwrap_or_default()
+                };
+                reply(&BotMessage::Suggestion { moves })?;
+            }
+            FrontendMessage::Play { mv } => {
+                if let Some(state) = &mut state {
+                    state.play(&mv);
+                }
+            }
+            FrontendMessage::NewPiece { piece } => {
+                if let Some(state) = &mut state {
+                    state.queue.push(piece);
+                }
+            }
+            FrontendMessage::Quit => break,
+        }
+    }
+    Ok(())
+}
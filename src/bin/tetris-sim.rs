@@ -0,0 +1,294 @@
+//! Headless batch simulation: plays seeded games through the engine with a chosen AI and
+//! reports statistics, so changes to rules, randomizers or AI weights can be measured
+//! without opening a window. The `tune` subcommand evolves the AI's evaluation weights.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread;
+
+use serde::Serialize;
+use tetris_rust::core::{GameMode, ai::Weights};
+use tetris_rust::app::{settings::AI_WEIGHTS_FILE, storage};
+use tetris_rust::sim::{self, GameResult, SimAi, SimConfig, Summary};
+use tetris_rust::sim::tune::{TuneSettings, Tuner};
+
+const USAGE: &str = "\
+Usage: tetris-sim [options]
+       tetris-sim tune [tune options]
+
+Options:
+  --games <n>          games to play (default 100)
+  --seed <n>           seed of the first game; game i uses seed + i (default 0)
+  --mode <name>        game mode, e.g. marathon, survival, cascade (default marathon)
+  --max-pieces <n>     stop games that last longer (default 1000)
+  --weights <w,...>    7 comma-separated weights for the built-in AI: aggregate height,
+                       lines, holes, bumpiness, wells, row transitions, column transitions
+  --bot <command>      play with an external TBP bot instead of the built-in AI
+  --output <path>      write per-game results (and the summary, for JSON); .csv or .json
+  --format <csv|json>  output format when the extension doesn't say
+
+Tune options:
+  --population <n>     individuals per generation (default 30)
+  --generations <n>    generations to reach, counting resumed ones (default 20)
+  --games <n>          seeded games per individual and generation (default 5)
+  --max-pieces <n>     piece limit of those games (default 300)
+  --mode <name>        game mode to tune for (default marathon)
+  --seed <n>           seed of the run (default 0)
+  --threads <n>        games played in parallel (default: all cores)
+  --checkpoint <path>  checkpoint written after every generation (default tune_checkpoint.json)
+  --resume             continue the run saved in the checkpoint
+  --output <path>      where the best weights go (default: the game's ai_weights.json)";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Csv,
+}
+
+struct Options {
+    config: SimConfig,
+    output: Option<(String, Format)>,
+}
+
+fn parse_mode(name: &str) -> Result<GameMode, String> {
+    let mode = GameMode::ALL
+        .into_iter()
+        .find(|mode| mode.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown mode '{name}'"))?;
+    // Puzzles and sandbox games need a board and piece list to start from
+    if matches!(mode, GameMode::Puzzle | GameMode::Sandbox) {
+        return Err(format!("{} mode can't be simulated", mode.name().to_lowercase()));
+    }
+    Ok(mode)
+}
+
+fn parse_weights(list: &str) -> Result<Weights, String> {
+    let values = list
+        .split(',')
+        .map(|value| value.trim().parse::<f64>().map_err(|_| format!("bad weight '{value}'")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let values: [f64; Weights::COUNT] = values
+        .try_into()
+        .map_err(|_| format!("expected {} weights", Weights::COUNT))?;
+    Ok(Weights::from_array(values))
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut config = SimConfig {
+        mode: GameMode::Marathon,
+        ai: SimAi::Builtin(Weights::default()),
+        games: 100,
+        seed: 0,
+        max_pieces: 1000,
+    };
+    let mut output = None;
+    let mut format = None;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--games" => config.games = value()?.parse().map_err(|_| "bad --games")?,
+            "--seed" => config.seed = value()?.parse().map_err(|_| "bad --seed")?,
+            "--mode" => config.mode = parse_mode(value()?)?,
+            "--max-pieces" => config.max_pieces = value()?.parse().map_err(|_| "bad --max-pieces")?,
+            "--weights" => config.ai = SimAi::Builtin(parse_weights(value()?)?),
+            "--bot" => config.ai = SimAi::External(value()?.clone()),
+            "--output" => output = Some(value()?.clone()),
+            "--format" => {
+                format = Some(match value()?.as_str() {
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    other => return Err(format!("unknown format '{other}'")),
+                })
+            }
+            other => return Err(format!("unknown option '{other}'")),
+        }
+    }
+
+    let output = output.map(|path| {
+        let format = format.unwrap_or(if path.ends_with(".csv") { Format::Csv } else { Format::Json });
+        (path, format)
+    });
+    Ok(Options { config, output })
+}
+
+struct TuneOptions {
+    settings: TuneSettings,
+    generations: u32,
+    threads: usize,
+    checkpoint: PathBuf,
+    resume: bool,
+    output: PathBuf,
+}
+
+fn parse_tune_args(args: &[String]) -> Result<TuneOptions, String> {
+    let mut options = TuneOptions {
+        settings: TuneSettings {
+            mode: GameMode::Marathon,
+            population: 30,
+            games: 5,
+            max_pieces: 300,
+            seed: 0,
+        },
+        generations: 20,
+        threads: thread::available_parallelism().map_or(1, |cores| cores.get()),
+        checkpoint: PathBuf::from("tune_checkpoint.json"),
+        resume: false,
+        output: storage::data_dir().join(AI_WEIGHTS_FILE),
+    };
+    let settings = &mut options.settings;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--population" => settings.population = value()?.parse().map_err(|_| "bad --population")?,
+            "--generations" => options.generations = value()?.parse().map_err(|_| "bad --generations")?,
+            "--games" => settings.games = value()?.parse().map_err(|_| "bad --games")?,
+            "--max-pieces" => settings.max_pieces = value()?.parse().map_err(|_| "bad --max-pieces")?,
+            "--mode" => settings.mode = parse_mode(value()?)?,
+            "--seed" => settings.seed = value()?.parse().map_err(|_| "bad --seed")?,
+            "--threads" => options.threads = value()?.parse().map_err(|_| "bad --threads")?,
+            "--checkpoint" => options.checkpoint = PathBuf::from(value()?),
+            "--resume" => options.resume = true,
+            "--output" => options.output = PathBuf::from(value()?),
+            other => return Err(format!("unknown option '{other}'")),
+        }
+    }
+    Ok(options)
+}
+
+fn tune(options: TuneOptions) -> Result<(), String> {
+    let mut tuner = if options.resume {
+        let tuner = Tuner::resume(&options.checkpoint)
+            .map_err(|err| format!("could not resume from {}: {err}", options.checkpoint.display()))?;
+        eprintln!("resuming after generation {}", tuner.checkpoint.generation);
+        tuner
+    } else {
+        Tuner::new(options.settings)
+    };
+
+    while tuner.checkpoint.generation < options.generations {
+        let leader = tuner.step(options.threads);
+        let best = tuner.checkpoint.best.unwrap_or(leader);
+        println!(
+            "generation {}: best {:.1} lines, best so far {:.1} on the validation games",
+            tuner.checkpoint.generation, leader.fitness, best.fitness
+        );
+
+        tuner
+            .save(&options.checkpoint)
+            .map_err(|err| format!("could not save checkpoint: {err}"))?;
+        if let Some(parent) = options.output.parent() {
+            fs::create_dir_all(parent).map_err(|err| format!("could not create {}: {err}", parent.display()))?;
+        }
+        let json = serde_json::to_string_pretty(&best.weights).map_err(|err| err.to_string())?;
+        fs::write(&options.output, json).map_err(|err| format!("could not write {}: {err}", options.output.display()))?;
+    }
+
+    if let Some(best) = tuner.checkpoint.best {
+        println!("best weights ({:.1} lines on the validation games): {:?}", best.fitness, best.weights.to_array());
+        println!("saved to {}", options.output.display());
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    mode: &'static str,
+    summary: &'a Summary,
+    games: &'a [GameResult],
+}
+
+fn to_csv(results: &[GameResult]) -> String {
+    let mut csv = String::from("seed,score,lines,level,pieces,topped_out,seconds,error\n");
+    for result in results {
+        let error = result.error.as_deref().unwrap_or("").replace('"', "'");
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{:.4},\"{}\"\n",
+            result.seed, result.score, result.lines, result.level, result.pieces, result.topped_out, result.seconds, error
+        ));
+    }
+    csv
+}
+
+fn print_summary(mode: GameMode, summary: &Summary) {
+    println!("{} games of {}", summary.games, mode.name());
+    println!("  lines   avg {:.1}", summary.average_lines);
+    println!("  pieces  avg {:.1}", summary.average_pieces);
+    println!(
+        "  score   avg {:.0}  min {}  median {}  p90 {}  max {}",
+        summary.average_score, summary.min_score, summary.median_score, summary.p90_score, summary.max_score
+    );
+    println!("  top-out rate {:.1}%", summary.top_out_rate * 100.0);
+    println!("  {:.1} pieces per second", summary.pps);
+    if summary.errors > 0 {
+        println!("  {} games ended by a bot error", summary.errors);
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    if args.first().map(String::as_str) == Some("tune") {
+        let result = parse_tune_args(&args[1..]).map(tune);
+        return match result {
+            Ok(Ok(())) => ExitCode::SUCCESS,
+            Ok(Err(err)) => {
+                eprintln!("tetris-sim: {err}");
+                ExitCode::FAILURE
+            }
+            Err(err) => {
+                eprintln!("tetris-sim: {err}\n\n{USAGE}");
+                ExitCode::from(2)
+            }
+        };
+    }
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("tetris-sim: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let config = &options.config;
+
+    let results = sim::run(config, |result| {
+        eprintln!(
+            "seed {}: {} lines, {} pieces{}",
+            result.seed,
+            result.lines,
+            result.pieces,
+            if result.topped_out { ", topped out" } else { "" }
+        );
+    });
+    let summary = Summary::new(&results);
+    print_summary(config.mode, &summary);
+
+    if let Some((path, format)) = &options.output {
+        let contents = match format {
+            Format::Csv => to_csv(&results),
+            Format::Json => {
+                let report = Report { mode: config.mode.name(), summary: &summary, games: &results };
+                match serde_json::to_string_pretty(&report) {
+                    Ok(json) => json,
+                    Err(err) => {
+                        eprintln!("tetris-sim: {err}");
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+        };
+        if let Err(err) = fs::write(path, contents) {
+            eprintln!("tetris-sim: could not write {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
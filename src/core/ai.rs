@@ -0,0 +1,459 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::{Action, Board, Game, Piece, Rules, SeededRng, TSpin, board::{BOARD_WIDTH, BOARD_HEIGHT}};
+
+/// Candidates the bot may pick from when it slips up
+const MISTAKE_POOL: usize = 5;
+
+/// Board features the evaluation is built from, measured after a placement and its line clear
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Features {
+    /// Sum of all column heights
+    pub aggregate_height: u32,
+    /// Lines cleared by the placement
+    pub lines: u32,
+    /// Empty cells with a filled cell somewhere above them
+    pub holes: u32,
+    /// Sum of height differences between neighbouring columns
+    pub bumpiness: u32,
+    /// Sum of well depths, counting 1 + 2 + ... + depth per well so deep wells weigh more
+    pub wells: u32,
+    /// Filled/empty changes along each row, the walls counting as filled
+    pub row_transitions: u32,
+    /// Filled/empty changes down each column, the floor counting as filled
+    pub column_transitions: u32,
+}
+
+impl Features {
+    pub fn measure(board: &Board, lines: u32) -> Self {
+        let heights = column_heights(board);
+        Self {
+            aggregate_height: heights.iter().sum(),
+            lines,
+            holes: count_holes(board),
+            bumpiness: heights.windows(2).map(|w| w[0].abs_diff(w[1])).sum(),
+            wells: well_sums(board),
+            row_transitions: row_transitions(board),
+            column_transitions: column_transitions(board),
+        }
+    }
+}
+
+/// Weights of the board evaluation; a higher score is a better board. The fields are public so
+/// the bot can be tuned.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Weights {
+    pub aggregate_height: f64,
+    pub lines: f64,
+    pub holes: f64,
+    pub bumpiness: f64,
+    pub wells: f64,
+    pub row_transitions: f64,
+    pub column_transitions: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            aggregate_height: -0.51,
+            lines: 0.76,
+            holes: -0.36,
+            bumpiness: -0.18,
+            wells: -0.1,
+            row_transitions: -0.1,
+            column_transitions: -0.3,
+        }
+    }
+}
+
+impl Weights {
+    /// Number of weights, in the field order used by `to_array` and `from_array`
+    pub const COUNT: usize = 7;
+
+    pub fn to_array(&self) -> [f64; Self::COUNT] {
+        [
+            self.aggregate_height,
+            self.lines,
+            self.holes,
+            self.bumpiness,
+            self.wells,
+            self.row_transitions,
+            self.column_transitions,
+        ]
+    }
+
+    pub fn from_array(values: [f64; Self::COUNT]) -> Self {
+        let [aggregate_height, lines, holes, bumpiness, wells, row_transitions, column_transitions] = values;
+        Self { aggregate_height, lines, holes, bumpiness, wells, row_transitions, column_transitions }
+    }
+
+    pub fn evaluate(&self, board: &Board, lines: u32) -> f64 {
+        self.score(&Features::measure(board, lines))
+    }
+
+    pub fn score(&self, features: &Features) -> f64 {
+        self.aggregate_height * features.aggregate_height as f64
+            + self.lines * features.lines as f64
+            + self.holes * features.holes as f64
+            + self.bumpiness * features.bumpiness as f64
+            + self.wells * features.wells as f64
+            + self.row_transitions * features.row_transitions as f64
+            + self.column_transitions * features.column_transitions as f64
+    }
+}
+
+/// Height of each column, counted from the floor to its highest filled cell
+pub fn column_heights(board: &Board) -> [u32; BOARD_WIDTH] {
+    let mut heights = [0; BOARD_WIDTH];
+    for (x, height) in heights.iter_mut().enumerate() {
+        if let Some(top) = (0..BOARD_HEIGHT).find(|&y| board.grid[y][x] != 0) {
+            *height = (BOARD_HEIGHT - top) as u32;
+        }
+    }
+    heights
+}
+
+/// Empty cells with a filled cell somewhere above them
+pub fn count_holes(board: &Board) -> u32 {
+    let mut holes = 0;
+    for x in 0..BOARD_WIDTH {
+        let mut covered = false;
+        for y in 0..BOARD_HEIGHT {
+            if board.grid[y][x] != 0 {
+                covered = true;
+            } else if covered {
+                holes += 1;
+            }
+        }
+    }
+    holes
+}
+
+fn filled(board: &Board, x: i32, y: usize) -> bool {
+    // Outside the side walls counts as filled
+    x < 0 || x >= BOARD_WIDTH as i32 || board.grid[y][x as usize] != 0
+}
+
+fn well_sums(board: &Board) -> u32 {
+    let mut sum = 0;
+    for x in 0..BOARD_WIDTH as i32 {
+        let mut depth = 0;
+        for y in 0..BOARD_HEIGHT {
+            if !filled(board, x, y) && filled(board, x - 1, y) && filled(board, x + 1, y) {
+                depth += 1;
+                sum += depth;
+            } else if filled(board, x, y) {
+                depth = 0;
+            }
+        }
+    }
+    sum
+}
+
+fn row_transitions(board: &Board) -> u32 {
+    let mut transitions = 0;
+    for y in 0..BOARD_HEIGHT {
+        for x in 0..=BOARD_WIDTH as i32 {
+            if filled(board, x - 1, y) != filled(board, x, y) {
+                transitions += 1;
+            }
+        }
+    }
+    transitions
+}
+
+fn column_transitions(board: &Board) -> u32 {
+    let mut transitions = 0;
+    for x in 0..BOARD_WIDTH {
+        let mut above = false;
+        for y in 0..BOARD_HEIGHT {
+            let here = board.grid[y][x] != 0;
+            if here != above {
+                transitions += 1;
+            }
+            above = here;
+        }
+        // The floor is filled
+        if !above {
+            transitions += 1;
+        }
+    }
+    transitions
+}
+
+/// A reachable final position and the inputs that get the piece there
+#[derive(Debug, Clone)]
+pub struct Placement {
+    /// Shortest input sequence, ending in a hard drop
+    pub actions: Vec<Action>,
+    /// The piece where it locks
+    pub piece: Piece,
+    /// Whether the last input is a rotation that makes the lock a T-spin
+    pub t_spin: TSpin,
+    /// Evaluation of the board it leaves; 0 until ranked
+    pub score: f64,
+}
+
+impl Placement {
+    /// Where the piece ends up and how: the cells it covers and its T-spin
+    fn landing(&self) -> (Vec<(i32, i32)>, TSpin) {
+        let mut cells = self.piece.blocks();
+        cells.sort_unstable();
+        (cells, self.t_spin)
+    }
+}
+
+/// Inputs the move generator explores, besides the final hard drop
+const MOVES: [Action; 5] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::RotateCw,
+    Action::RotateCcw,
+    Action::SoftDrop,
+];
+
+/// Where `action` takes `piece`, following the engine's movement and kick rules
+fn step(board: &Board, piece: &Piece, action: Action) -> Option<Piece> {
+    let scale = piece.scale;
+    match action {
+        Action::MoveLeft => board.can_move(piece, -scale, 0).then(|| piece.with_offset(-scale, 0)),
+        Action::MoveRight => board.can_move(piece, scale, 0).then(|| piece.with_offset(scale, 0)),
+        Action::SoftDrop => board.can_move(piece, 0, scale).then(|| piece.with_offset(0, scale)),
+        Action::RotateCw => Rules::try_wall_kick(board, &piece.rotated_cw()),
+        Action::RotateCcw => Rules::try_wall_kick(board, &piece.rotated_ccw()),
+        _ => None,
+    }
+}
+
+/// Every placement of `piece` the player could reach from where it stands, found by a
+/// breadth-first search over positions and rotations. Besides plain drops this finds soft-drop
+/// tucks under overhangs and spins into slots, each with the fewest inputs that reach it.
+/// `prefix` is prepended to each action list (for example a hold). The placements are unscored.
+pub fn placements(board: &Board, piece: &Piece, prefix: &[Action]) -> Vec<Placement> {
+    let rotation = |action: Option<&Action>| matches!(action, Some(Action::RotateCw | Action::RotateCcw));
+    // Reaching a position by a rotation can make a T-spin where moving there can't
+    let state = |piece: &Piece, rotated: bool| (piece.x, piece.y, piece.rotation, rotated);
+
+    let mut visited = HashSet::from([state(piece, false)]);
+    let mut queue = VecDeque::from([(piece.clone(), prefix.to_vec())]);
+    // Rotations covering the same cells lock the same way, so they count once unless one of
+    // them is a T-spin
+    let mut landings = HashSet::new();
+    let mut found = Vec::new();
+
+    while let Some((current, actions)) = queue.pop_front() {
+        let distance = board.drop_distance(&current);
+        let dropped = current.with_offset(0, distance as i32);
+        // A hard drop that moves the piece ends the spin, as in the engine
+        let t_spin = if distance == 0 && rotation(actions.last()) {
+            Rules::detect_t_spin(board, &dropped)
+        } else {
+            TSpin::None
+        };
+        let mut cells = dropped.blocks();
+        cells.sort_unstable();
+        // The queue is in order of input count, so the first path to a landing is the shortest
+        if landings.insert((cells, t_spin)) {
+            let mut actions = actions.clone();
+            actions.push(Action::HardDrop);
+            found.push(Placement { actions, piece: dropped, t_spin, score: 0.0 });
+        }
+
+        for action in MOVES {
+            let Some(next) = step(board, &current, action) else {
+                continue;
+            };
+            if visited.insert(state(&next, rotation(Some(&action)))) {
+                let mut path = actions.clone();
+                path.push(action);
+                queue.push_back((next, path));
+            }
+        }
+    }
+    found
+}
+
+/// Every placement of `current` and, when given, of `hold` (the piece a hold would bring in,
+/// already at its spawn position), unscored. Hold placements start with [`Action::Hold`].
+pub fn legal_placements(board: &Board, current: &Piece, hold: Option<&Piece>) -> Vec<Placement> {
+    let mut candidates = placements(board, current, &[]);
+    if let Some(piece) = hold.filter(|piece| board.can_place(piece)) {
+        candidates.extend(placements(board, piece, &[Action::Hold]));
+    }
+    candidates
+}
+
+/// [`legal_placements`] scored by `weights`, best first. Of placements leaving the same
+/// board, a T-spin ranks first: it scores and attacks more.
+pub fn ranked_placements(board: &Board, current: &Piece, hold: Option<&Piece>, weights: &Weights) -> Vec<Placement> {
+    let mut candidates = legal_placements(board, current, hold);
+    for placement in &mut candidates {
+        let mut result = board.clone();
+        result.lock_piece(&placement.piece);
+        let lines = result.clear_lines();
+        placement.score = weights.evaluate(&result, lines);
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(b.t_spin.cmp(&a.t_spin)));
+    candidates
+}
+
+/// Highest scoring placement of `current` or `hold`, with the inputs that reach it
+pub fn best_placement(board: &Board, current: &Piece, hold: Option<&Piece>, weights: &Weights) -> Option<Placement> {
+    ranked_placements(board, current, hold, weights).into_iter().next()
+}
+
+/// The piece a hold would bring into play in `game`, if holding is allowed
+pub fn hold_option(game: &Game) -> Option<Piece> {
+    if !game.can_hold {
+        return None;
+    }
+    // An empty hold slot brings in the next piece
+    let shape = game.hold_piece.as_ref().unwrap_or(&game.next_piece).shape;
+    Some(game.spawn_piece(shape))
+}
+
+/// Computer opponent. It plans a placement for each piece and then feeds the inputs to its
+/// game at a fixed pace, so it plays by exactly the same rules as a human.
+pub struct Bot {
+    pub weights: Weights,
+    /// Pieces per second
+    pub pps: f64,
+    /// 0.0 to 1.0; below 1.0 the bot sometimes settles for a worse placement
+    pub skill: f64,
+    plan: VecDeque<Action>,
+    /// Where the plan leads, to steer for again when gravity moves the piece
+    target: Option<(Vec<(i32, i32)>, TSpin)>,
+    /// The piece's position, row and rotation after the last input; the plan's remaining
+    /// inputs only hold from there
+    expected: Option<(i32, i32, usize)>,
+    /// Locked piece count when the plan was made
+    planned_for: u32,
+    /// Seconds between inputs of the current plan
+    step: f64,
+    /// Seconds until the next input
+    timer: f64,
+    rng: SeededRng,
+}
+
+impl Bot {
+    pub fn new(pps: f64, skill: f64) -> Self {
+        Self {
+            weights: Weights::default(),
+            pps: pps.max(0.1),
+            skill: skill.clamp(0.0, 1.0),
+            plan: VecDeque::new(),
+            target: None,
+            expected: None,
+            planned_for: 0,
+            step: 0.0,
+            timer: 0.0,
+            rng: SeededRng::from_entropy(),
+        }
+    }
+
+    pub fn with_weights(mut self, weights: Weights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Picks the placement for the current piece: the best, or with probability `1 - skill`
+    /// one of the next few best
+    pub fn choose(&mut self, game: &Game) -> Option<Placement> {
+        let hold = hold_option(game);
+        if !self.rng.gen_bool(1.0 - self.skill) {
+            return best_placement(&game.board, &game.current_piece, hold.as_ref(), &self.weights);
+        }
+
+        let mut candidates = ranked_placements(&game.board, &game.current_piece, hold.as_ref(), &self.weights);
+        if candidates.is_empty() {
+            return None;
+        }
+        let pick = self.rng.gen_range(0..candidates.len().min(MISTAKE_POOL));
+        Some(candidates.swap_remove(pick))
+    }
+
+    /// The inputs that now reach the planned landing, from wherever gravity has taken the
+    /// piece; empty if it can no longer get there
+    fn replan(&self, game: &Game) -> VecDeque<Action> {
+        let hold = hold_option(game);
+        legal_placements(&game.board, &game.current_piece, hold.as_ref())
+            .into_iter()
+            .find(|placement| self.target.as_ref() == Some(&placement.landing()))
+            .map(|placement| placement.actions.into())
+            .unwrap_or_default()
+    }
+
+    /// Advances the bot by `dt` seconds, applying its inputs to `game` as they come due
+    pub fn update(&mut self, game: &mut Game, dt: f64) {
+        let state = |piece: &Piece| (piece.x, piece.y, piece.rotation);
+        // A plan is only good for the piece it was made for
+        if !game.piece_active() || self.planned_for != game.pieces {
+            self.plan.clear();
+        }
+        if !game.piece_active() {
+            return;
+        }
+        // Gravity moved the piece between inputs, so the rest of the plan (a tuck, a spin) may
+        // no longer lead where it should
+        if !self.plan.is_empty() && self.expected != Some(state(&game.current_piece)) {
+            self.plan = self.replan(game);
+        }
+        if self.plan.is_empty() {
+            let Some(placement) = self.choose(game) else {
+                return;
+            };
+            self.target = Some(placement.landing());
+            self.plan = placement.actions.into();
+            self.planned_for = game.pieces;
+            // The inputs of one piece are spread evenly over its share of time
+            self.step = 1.0 / self.pps / self.plan.len() as f64;
+            self.timer = self.step;
+        }
+
+        self.timer -= dt;
+        while self.timer <= 0.0 {
+            let Some(action) = self.plan.pop_front() else {
+                break;
+            };
+            game.apply(action);
+            self.timer += self.step;
+        }
+        self.expected = Some(state(&game.current_piece));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tetromino::TetrominoShape;
+
+    /// A T-spin double slot at the bottom of an otherwise empty board
+    fn tsd_board() -> Board {
+        let mut board = Board::new();
+        for (y, row) in ["GGG.......", "GG...GGGGG", "GGG.GGGGGG"].iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                if cell == 'G' {
+                    board.grid[BOARD_HEIGHT - 3 + y][x] = 8;
+                }
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn spin_and_plain_drop_into_same_cells_are_both_kept() {
+        let found = placements(&tsd_board(), &Piece::new(TetrominoShape::T), &[]);
+        let landings: Vec<_> = found.iter().map(Placement::landing).collect();
+
+        // Upright T standing in the slot's well: reachable by a plain drop and by a spin
+        let upright = vec![(3, 17), (3, 18), (3, 19), (4, 18)];
+        assert!(landings.contains(&(upright.clone(), TSpin::None)));
+        assert!(landings.iter().any(|(cells, t_spin)| *cells == upright && *t_spin != TSpin::None));
+
+        // The T-spin double itself
+        let tsd = vec![(2, 18), (3, 18), (3, 19), (4, 18)];
+        assert!(landings.contains(&(tsd, TSpin::Full)));
+    }
+}
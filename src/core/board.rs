@@ -1,17 +1,67 @@
+use serde::{Deserialize, Serialize};
 use super::piece::Piece;
 
 pub const BOARD_WIDTH: usize = 10;
 pub const BOARD_HEIGHT: usize = 20;
+/// Hidden rows above the visible field that a rising stack can be pushed into
+pub const BUFFER_HEIGHT: usize = 20;
 
+/// Color id used for garbage blocks
+pub const GARBAGE_COLOR: u8 = 8;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     pub grid: [[u8; BOARD_WIDTH]; BOARD_HEIGHT],
+    /// Board time at which each cell was filled, moved along with its row
+    #[serde(default)]
+    pub lock_time: [[f64; BOARD_WIDTH]; BOARD_HEIGHT],
+    /// Game time used to stamp locked cells
+    #[serde(default)]
+    pub time: f64,
+    /// Lock group of each cell (0 = none); blocks locked together stay connected
+    #[serde(default)]
+    pub groups: [[u32; BOARD_WIDTH]; BOARD_HEIGHT],
+    /// Id handed to the next locked piece or inserted row
+    #[serde(default)]
+    next_group: u32,
+    /// Rows pushed above the visible field, the last one right above `grid[0]`
+    #[serde(default)]
+    pub buffer: [[u8; BOARD_WIDTH]; BUFFER_HEIGHT],
 }
 
 impl Board {
     pub fn new() -> Self {
         Self {
             grid: [[0; BOARD_WIDTH]; BOARD_HEIGHT],
+            lock_time: [[0.0; BOARD_WIDTH]; BOARD_HEIGHT],
+            time: 0.0,
+            groups: [[0; BOARD_WIDTH]; BOARD_HEIGHT],
+            next_group: 0,
+            buffer: [[0; BOARD_WIDTH]; BUFFER_HEIGHT],
+        }
+    }
+
+    fn new_group(&mut self) -> u32 {
+        self.next_group = self.next_group.wrapping_add(1).max(1);
+        self.next_group
+    }
+
+    pub fn advance_time(&mut self, dt: f64) {
+        self.time += dt;
+    }
+
+    /// Seconds since the cell at (x, y) was filled
+    pub fn cell_age(&self, x: usize, y: usize) -> f64 {
+        self.time - self.lock_time[y][x]
+    }
+
+    /// Color of the cell at column `x` of row `y`; rows above the field are in the buffer, and
+    /// anything above the buffer is empty
+    pub fn cell(&self, x: usize, y: i32) -> u8 {
+        if y >= 0 {
+            return self.grid[y as usize][x];
         }
+        usize::try_from(BUFFER_HEIGHT as i32 + y).map_or(0, |row| self.buffer[row][x])
     }
 
     pub fn can_place(&self, piece: &Piece) -> bool {
@@ -26,7 +76,7 @@ impl Board {
             if nx < 0 || nx >= BOARD_WIDTH as i32 || ny >= BOARD_HEIGHT as i32 {
                 return false;
             }
-            if ny >= 0 && self.grid[ny as usize][nx as usize] != 0 {
+            if self.cell(nx as usize, ny) != 0 {
                 return false;
             }
         }
@@ -34,9 +84,17 @@ impl Board {
     }
 
     pub fn lock_piece(&mut self, piece: &Piece) {
+        let group = self.new_group();
         for (x, y) in piece.blocks() {
-            if y >= 0 && y < BOARD_HEIGHT as i32 && x >= 0 && x < BOARD_WIDTH as i32 {
+            if x < 0 || x >= BOARD_WIDTH as i32 || y >= BOARD_HEIGHT as i32 {
+                continue;
+            }
+            if y >= 0 {
                 self.grid[y as usize][x as usize] = piece.color_id;
+                self.lock_time[y as usize][x as usize] = self.time;
+                self.groups[y as usize][x as usize] = group;
+            } else if let Ok(row) = usize::try_from(BUFFER_HEIGHT as i32 + y) {
+                self.buffer[row][x as usize] = piece.color_id;
             }
         }
     }
@@ -44,6 +102,8 @@ impl Board {
     pub fn clear_lines(&mut self) -> u32 {
         let mut cleared = 0;
         let mut new_grid = [[0u8; BOARD_WIDTH]; BOARD_HEIGHT];
+        let mut new_lock_time = [[0.0; BOARD_WIDTH]; BOARD_HEIGHT];
+        let mut new_groups = [[0; BOARD_WIDTH]; BOARD_HEIGHT];
         let mut write_row = BOARD_HEIGHT - 1;
 
         for read_row in (0..BOARD_HEIGHT).rev() {
@@ -51,16 +111,193 @@ impl Board {
                 cleared += 1;
             } else {
                 new_grid[write_row] = self.grid[read_row];
-                if write_row > 0 {
-                    write_row -= 1;
-                }
+                new_lock_time[write_row] = self.lock_time[read_row];
+                new_groups[write_row] = self.groups[read_row];
+                write_row = write_row.saturating_sub(1);
             }
         }
 
+        // Rows waiting in the buffer come down by as many rows as cleared
+        let mut new_buffer = [[0; BOARD_WIDTH]; BUFFER_HEIGHT];
+        for (i, row) in self.buffer.into_iter().enumerate() {
+            let target = i + cleared as usize;
+            if target < BUFFER_HEIGHT {
+                new_buffer[target] = row;
+            } else if row.iter().any(|&c| c != 0) {
+                let group = self.new_group();
+                new_grid[target - BUFFER_HEIGHT] = row;
+                new_lock_time[target - BUFFER_HEIGHT] = [self.time; BOARD_WIDTH];
+                new_groups[target - BUFFER_HEIGHT] = [group; BOARD_WIDTH];
+            }
+        }
+
+        self.buffer = new_buffer;
         self.grid = new_grid;
+        self.lock_time = new_lock_time;
+        self.groups = new_groups;
         cleared
     }
 
+    /// Sticky-gravity line clear: full rows are emptied without shifting the rows above,
+    /// then every connected unit falls until it rests. Units landing can fill new rows,
+    /// which clear in turn. Returns the lines cleared at each step of the chain.
+    pub fn cascade(&mut self) -> Vec<u32> {
+        let mut chain = Vec::new();
+        loop {
+            let full: Vec<usize> = (0..BOARD_HEIGHT)
+                .filter(|&y| self.grid[y].iter().all(|&c| c != 0))
+                .collect();
+            if full.is_empty() {
+                break;
+            }
+            for &y in &full {
+                self.grid[y] = [0; BOARD_WIDTH];
+                self.groups[y] = [0; BOARD_WIDTH];
+            }
+            chain.push(full.len() as u32);
+            self.settle();
+        }
+        chain
+    }
+
+    /// Drops every connected unit as far as it goes, lowest units first, until nothing moves
+    fn settle(&mut self) {
+        loop {
+            let mut units = self.units();
+            // Resolve from the bottom up so units fall onto already-settled ones
+            units.sort_by_key(|unit| std::cmp::Reverse(unit.iter().map(|&(_, y)| y).max()));
+
+            let mut moved = false;
+            for unit in units {
+                let mut distance = 0;
+                while unit.iter().all(|&(x, y)| {
+                    let ny = y + distance + 1;
+                    ny < BOARD_HEIGHT && (self.grid[ny][x] == 0 || unit.contains(&(x, ny)))
+                }) {
+                    distance += 1;
+                }
+                if distance > 0 {
+                    self.shift_unit(&unit, distance);
+                    moved = true;
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+    }
+
+    /// Moves the cells of a unit `distance` rows down, along with their lock data
+    fn shift_unit(&mut self, unit: &[(usize, usize)], distance: usize) {
+        let cells: Vec<_> = unit
+            .iter()
+            .map(|&(x, y)| (x, y, self.grid[y][x], self.lock_time[y][x], self.groups[y][x]))
+            .collect();
+        for &(x, y) in unit {
+            self.grid[y][x] = 0;
+            self.groups[y][x] = 0;
+        }
+        for (x, y, color, time, group) in cells {
+            self.grid[y + distance][x] = color;
+            self.lock_time[y + distance][x] = time;
+            self.groups[y + distance][x] = group;
+        }
+    }
+
+    /// Filled cells split into connected units. Neighbours are connected when they were
+    /// locked together or share a color.
+    fn units(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = [[false; BOARD_WIDTH]; BOARD_HEIGHT];
+        let mut units = Vec::new();
+
+        for y in 0..BOARD_HEIGHT {
+            for x in 0..BOARD_WIDTH {
+                if self.grid[y][x] == 0 || seen[y][x] {
+                    continue;
+                }
+                seen[y][x] = true;
+                let mut unit = vec![(x, y)];
+                let mut i = 0;
+                while i < unit.len() {
+                    let (cx, cy) = unit[i];
+                    let neighbours = [
+                        (cx.wrapping_sub(1), cy),
+                        (cx + 1, cy),
+                        (cx, cy.wrapping_sub(1)),
+                        (cx, cy + 1),
+                    ];
+                    for (nx, ny) in neighbours {
+                        if nx < BOARD_WIDTH && ny < BOARD_HEIGHT && !seen[ny][nx] && self.connected((cx, cy), (nx, ny)) {
+                            seen[ny][nx] = true;
+                            unit.push((nx, ny));
+                        }
+                    }
+                    i += 1;
+                }
+                units.push(unit);
+            }
+        }
+        units
+    }
+
+    fn connected(&self, (ax, ay): (usize, usize), (bx, by): (usize, usize)) -> bool {
+        let (a, b) = (self.grid[ay][ax], self.grid[by][bx]);
+        if a == 0 || b == 0 {
+            return false;
+        }
+        let group = self.groups[ay][ax];
+        a == b || (group != 0 && group == self.groups[by][bx])
+    }
+
+    /// Pushes the whole stack up and fills the bottom with `rows`. The top rows of the field
+    /// move into the buffer above it. Returns false if a block was pushed past the buffer.
+    pub fn insert_rows(&mut self, rows: &[[u8; BOARD_WIDTH]]) -> bool {
+        let count = rows.len().min(BOARD_HEIGHT);
+        let mut above: Vec<[u8; BOARD_WIDTH]> = self.buffer.iter().chain(&self.grid[..count]).copied().collect();
+        let overflow = above.drain(..count).flatten().any(|c| c != 0);
+        self.buffer.copy_from_slice(&above);
+
+        self.grid.copy_within(count.., 0);
+        self.lock_time.copy_within(count.., 0);
+        self.groups.copy_within(count.., 0);
+        for (i, row) in rows.iter().take(count).enumerate() {
+            let group = self.new_group();
+            self.grid[BOARD_HEIGHT - count + i] = *row;
+            self.lock_time[BOARD_HEIGHT - count + i] = [self.time; BOARD_WIDTH];
+            self.groups[BOARD_HEIGHT - count + i] = [group; BOARD_WIDTH];
+        }
+        !overflow
+    }
+
+    /// Pushes `lines` garbage rows in from the bottom, all open at column `hole`.
+    /// Returns false if a block was pushed past the buffer.
+    pub fn insert_garbage(&mut self, lines: usize, hole: usize) -> bool {
+        let rows = vec![Self::garbage_row(hole); lines];
+        self.insert_rows(&rows)
+    }
+
+    /// A full garbage row with a single hole at column `hole`
+    pub fn garbage_row(hole: usize) -> [u8; BOARD_WIDTH] {
+        let mut row = [GARBAGE_COLOR; BOARD_WIDTH];
+        row[hole] = 0;
+        row
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.grid.iter().chain(&self.buffer).all(|row| row.iter().all(|&c| c == 0))
+    }
+
+    /// Empties the top `rows` rows and the buffer, leaving the rest of the stack in place
+    pub fn clear_top(&mut self, rows: usize) {
+        self.buffer = [[0; BOARD_WIDTH]; BUFFER_HEIGHT];
+        for row in self.grid.iter_mut().take(rows) {
+            *row = [0; BOARD_WIDTH];
+        }
+        for row in self.groups.iter_mut().take(rows) {
+            *row = [0; BOARD_WIDTH];
+        }
+    }
+
     pub fn drop_distance(&self, piece: &Piece) -> usize {
         let mut distance = 0;
         while self.can_move(piece, 0, (distance + 1) as i32) {
@@ -69,3 +306,52 @@ impl Board {
         distance
     }
 }
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rising_rows_top_out_only_past_the_buffer() {
+        let mut board = Board::new();
+        for _ in 0..BOARD_HEIGHT + BUFFER_HEIGHT {
+            assert!(board.insert_garbage(1, 0));
+        }
+        assert_eq!(board.cell(1, -(BUFFER_HEIGHT as i32)), GARBAGE_COLOR);
+        assert!(!board.insert_garbage(1, 0));
+    }
+
+    #[test]
+    fn cleared_lines_bring_buffered_rows_back_down() {
+        let mut board = Board::new();
+        assert!(board.insert_garbage(BOARD_HEIGHT, 0));
+        assert!(board.insert_garbage(2, 1));
+        assert_eq!(board.buffer[BUFFER_HEIGHT - 1], Board::garbage_row(0));
+
+        // Fill the hole of the bottom row
+        board.grid[BOARD_HEIGHT - 1][1] = GARBAGE_COLOR;
+        assert_eq!(board.clear_lines(), 1);
+        assert_eq!(board.grid[0], Board::garbage_row(0));
+        assert_eq!(board.buffer[BUFFER_HEIGHT - 1], Board::garbage_row(0));
+        assert_eq!(board.cell(0, -2), 0);
+    }
+
+    #[test]
+    fn cascade_chains_rows_filled_by_falling_units() {
+        let mut board = Board::new();
+        board.grid[BOARD_HEIGHT - 1] = [1; BOARD_WIDTH];
+        // A row missing its last cell, and a lone block above the gap
+        board.grid[BOARD_HEIGHT - 2] = [2, 2, 2, 2, 2, 2, 2, 2, 2, 0];
+        board.grid[BOARD_HEIGHT - 3][BOARD_WIDTH - 1] = 3;
+
+        // The full row clears, both units drop into the bottom row and fill it
+        assert_eq!(board.cascade(), vec![1, 1]);
+        assert!(board.is_empty());
+    }
+}
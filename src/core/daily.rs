@@ -0,0 +1,44 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::{GameMode, rng::SeededRng};
+
+/// Modes the daily challenge draws from
+const DAILY_MODES: [GameMode; 8] = [
+    GameMode::Marathon,
+    GameMode::Survival,
+    GameMode::Invisible,
+    GameMode::Fading,
+    GameMode::Big,
+    GameMode::Mirror,
+    GameMode::UpsideDown,
+    GameMode::Cascade,
+];
+
+/// Ruleset of a daily challenge. Everything is derived from the date alone, so every
+/// player gets the same mode and piece sequence on the same day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyChallenge {
+    /// Date as YYYY-MM-DD, also the key of the local history
+    pub date: String,
+    pub seed: u64,
+    pub mode: GameMode,
+}
+
+impl DailyChallenge {
+    pub fn for_date(year: i32, month: u32, day: u32) -> Self {
+        let date = format!("{:04}-{:02}-{:02}", year, month, day);
+        let day_number = year as u64 * 10_000 + month as u64 * 100 + day as u64;
+
+        // Mix the date once so neighbouring days get unrelated seeds
+        let mut rng = SeededRng::new(day_number);
+        let seed = rng.gen();
+        let mode = DAILY_MODES[rng.gen_range(0..DAILY_MODES.len())];
+
+        Self { date, seed, mode }
+    }
+
+    /// Seed for garbage holes, kept apart from the piece sequence
+    pub fn garbage_seed(&self) -> u64 {
+        self.seed.rotate_left(32) ^ 0xDA11_7C4A_1E46_E000
+    }
+}
@@ -0,0 +1,529 @@
+use std::fmt::Write;
+use super::{Board, board::{BOARD_WIDTH, BOARD_HEIGHT, GARBAGE_COLOR}, tetromino::TetrominoShape};
+
+/// Rows of a fumen field above the floor; one more row below it holds garbage waiting to rise
+const FIELD_TOP: usize = 23;
+const FIELD_BLOCKS: usize = (FIELD_TOP + 1) * BOARD_WIDTH;
+const ENCODE_TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const COMMENT_TABLE: &[u8; 95] = b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+const COMMENT_BASE: u32 = 96;
+/// Fumen's block code for garbage; pieces take 1 to 7
+const GRAY: u8 = 8;
+
+/// Cells in fumen's order: the rows from the top down, then the garbage row
+type Field = [u8; FIELD_BLOCKS];
+
+fn block_code(shape: TetrominoShape) -> u8 {
+    match shape {
+        TetrominoShape::I => 1,
+        TetrominoShape::L => 2,
+        TetrominoShape::O => 3,
+        TetrominoShape::Z => 4,
+        TetrominoShape::T => 5,
+        TetrominoShape::J => 6,
+        TetrominoShape::S => 7,
+    }
+}
+
+fn block_shape(code: u8) -> Option<TetrominoShape> {
+    TetrominoShape::ALL.into_iter().find(|&shape| block_code(shape) == code)
+}
+
+/// Field index of a cell, with y counted up from the floor and -1 for the garbage row
+fn index(x: i32, y: i32) -> usize {
+    (FIELD_TOP as i32 - 1 - y) as usize * BOARD_WIDTH + x as usize
+}
+
+fn in_field(x: i32, y: i32) -> bool {
+    (0..BOARD_WIDTH as i32).contains(&x) && (0..FIELD_TOP as i32).contains(&y)
+}
+
+/// Offset from the position fumen stores to the rotation center, for the orientations where
+/// the two differ
+fn nudge(shape: TetrominoShape, rotation: u8) -> (i32, i32) {
+    match (shape, rotation) {
+        (TetrominoShape::O, 0) | (TetrominoShape::S, 0) | (TetrominoShape::Z, 0) => (0, 1),
+        (TetrominoShape::O, 2) | (TetrominoShape::I, 2) | (TetrominoShape::Z, 3) => (-1, 0),
+        (TetrominoShape::O, 3) => (-1, 1),
+        (TetrominoShape::I, 3) => (0, 1),
+        (TetrominoShape::S, 1) => (1, 0),
+        _ => (0, 0),
+    }
+}
+
+/// Fumen numbers orientations from upside down: reverse, right, spawn, left
+fn rotation_code(rotation: u8) -> u32 {
+    [2, 1, 0, 3][rotation as usize % 4]
+}
+
+fn rotation_of(code: u32) -> u8 {
+    [2, 1, 0, 3][code as usize % 4]
+}
+
+/// A piece placed on a page, by its rotation center with y counted up from the floor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub shape: TetrominoShape,
+    /// Quarter turns clockwise from the spawn orientation
+    pub rotation: u8,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Placement {
+    /// The cells covered, y counted up from the floor
+    pub fn cells(&self) -> [(i32, i32); 4] {
+        let mut cells = match self.shape {
+            TetrominoShape::I => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+            TetrominoShape::T => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+            TetrominoShape::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+            TetrominoShape::L => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+            TetrominoShape::J => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+            TetrominoShape::S => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+            TetrominoShape::Z => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+        };
+        for cell in &mut cells {
+            for _ in 0..self.rotation % 4 {
+                *cell = (cell.1, -cell.0);
+            }
+            *cell = (self.x + cell.0, self.y + cell.1);
+        }
+        cells
+    }
+
+    /// The placement covering `blocks`, given as board cells, if they make up that piece
+    pub fn from_blocks(shape: TetrominoShape, blocks: &[(i32, i32)]) -> Option<Self> {
+        let cells: Vec<(i32, i32)> = blocks.iter().map(|&(x, y)| (x, BOARD_HEIGHT as i32 - 1 - y)).collect();
+        if cells.len() != 4 {
+            return None;
+        }
+        (0..4).flat_map(|rotation| (0..4).map(move |k| (rotation, k))).find_map(|(rotation, k)| {
+            let (dx, dy) = Self { shape, rotation, x: 0, y: 0 }.cells()[k];
+            let placement = Self { shape, rotation, x: cells[0].0 - dx, y: cells[0].1 - dy };
+            let covers = placement.cells().iter().all(|cell| cells.contains(cell));
+            (covers && placement.position().is_some()).then_some(placement)
+        })
+    }
+
+    /// The position fumen stores, if the piece lies within the field
+    fn position(&self) -> Option<u32> {
+        if !self.cells().iter().all(|&(x, y)| in_field(x, y)) {
+            return None;
+        }
+        let (dx, dy) = nudge(self.shape, self.rotation);
+        let (x, y) = (self.x - dx, self.y - dy);
+        ((0..BOARD_WIDTH as i32).contains(&x) && (-1..FIELD_TOP as i32).contains(&y)).then(|| index(x, y) as u32)
+    }
+
+    fn from_position(shape: TetrominoShape, rotation: u8, position: u32) -> Self {
+        let (dx, dy) = nudge(shape, rotation);
+        let x = (position as usize % BOARD_WIDTH) as i32;
+        let y = FIELD_TOP as i32 - 1 - (position as usize / BOARD_WIDTH) as i32;
+        Self { shape, rotation, x: x + dx, y: y + dy }
+    }
+}
+
+/// One page of a diagram: a field, optionally a piece placed on it, and a comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    field: Field,
+    pub piece: Option<Placement>,
+    pub comment: String,
+}
+
+impl Page {
+    pub fn new(board: &Board) -> Self {
+        let mut field = [0; FIELD_BLOCKS];
+        for (row, cells) in board.grid.iter().enumerate() {
+            let y = (BOARD_HEIGHT - 1 - row) as i32;
+            for (x, &cell) in cells.iter().enumerate() {
+                field[index(x as i32, y)] = match cell {
+                    0 => 0,
+                    GARBAGE_COLOR => GRAY,
+                    id => TetrominoShape::from_color_id(id).map_or(GRAY, block_code),
+                };
+            }
+        }
+        Self { field, piece: None, comment: String::new() }
+    }
+
+    /// The field as a board; the garbage row under the floor is left out
+    pub fn board(&self) -> Result<Board, String> {
+        if self.field[..(FIELD_TOP - BOARD_HEIGHT) * BOARD_WIDTH].iter().any(|&cell| cell != 0) {
+            return Err(format!("the stack is taller than {BOARD_HEIGHT} rows"));
+        }
+        let mut board = Board::new();
+        for (row, cells) in board.grid.iter_mut().enumerate() {
+            let y = (BOARD_HEIGHT - 1 - row) as i32;
+            for (x, cell) in cells.iter_mut().enumerate() {
+                *cell = match self.field[index(x as i32, y)] {
+                    0 => 0,
+                    code => block_shape(code).map_or(GARBAGE_COLOR, |shape| shape.color_id()),
+                };
+            }
+        }
+        Ok(board)
+    }
+
+    /// The page that follows once the piece locks and full rows clear
+    pub fn next(&self) -> Self {
+        Self { field: self.locked_field(), piece: None, comment: String::new() }
+    }
+
+    fn locked_field(&self) -> Field {
+        let mut field = self.field;
+        if let Some(piece) = self.piece {
+            for (x, y) in piece.cells() {
+                field[index(x, y)] = block_code(piece.shape);
+            }
+        }
+        let play = FIELD_TOP * BOARD_WIDTH;
+        let kept: Vec<&[u8]> = field[..play].chunks(BOARD_WIDTH).filter(|row| row.contains(&0)).collect();
+        let mut cleared = [0; FIELD_BLOCKS];
+        cleared[play - kept.len() * BOARD_WIDTH..play].copy_from_slice(&kept.concat());
+        cleared[play..].copy_from_slice(&field[play..]);
+        cleared
+    }
+}
+
+/// Splits a diagram, possibly inside a link, into its version and data
+fn split_version(text: &str) -> Option<(&str, &str)> {
+    let at = text.find('@')?;
+    let head = text.get(at.checked_sub(4)?..at)?;
+    let mut chars = head.chars();
+    let valid = matches!(chars.next(), Some('v' | 'm' | 'd' | 'D')) && chars.all(|c| c.is_ascii_digit());
+    valid.then(|| (&head[1..], &text[at + 1..]))
+}
+
+/// Whether the text looks like a fumen diagram or a link to one
+pub fn is_fumen(text: &str) -> bool {
+    split_version(text.trim()).is_some()
+}
+
+struct Reader {
+    values: Vec<u32>,
+    position: usize,
+}
+
+impl Reader {
+    fn poll(&mut self, digits: usize) -> Result<u32, String> {
+        let digits_read = self.values.get(self.position..self.position + digits).ok_or("the diagram is cut short")?;
+        self.position += digits;
+        Ok(digits_read.iter().rev().fold(0, |value, &digit| value * 64 + digit))
+    }
+
+    fn done(&self) -> bool {
+        self.position >= self.values.len()
+    }
+}
+
+fn push(values: &mut Vec<u32>, mut value: u32, digits: usize) {
+    for _ in 0..digits {
+        values.push(value % 64);
+        value /= 64;
+    }
+}
+
+/// Reads every page of a v115 diagram
+pub fn decode(text: &str) -> Result<Vec<Page>, String> {
+    let (version, data) = split_version(text.trim()).ok_or("not a fumen diagram")?;
+    if version != "115" {
+        return Err(format!("fumen version {version} is not supported, only 115"));
+    }
+    let values = data
+        .chars()
+        .take_while(|&c| c != '#' && c != '&')
+        .filter(|&c| c != '?' && !c.is_whitespace())
+        .map(|c| {
+            ENCODE_TABLE
+                .iter()
+                .position(|&digit| digit as char == c)
+                .map(|digit| digit as u32)
+                .ok_or_else(|| format!("bad fumen character '{c}'"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut reader = Reader { values, position: 0 };
+
+    let mut pages: Vec<Page> = Vec::new();
+    let mut previous = [0; FIELD_BLOCKS];
+    let mut comment = String::new();
+    // Pages still to come that keep the field as it is
+    let mut repeat = 0;
+    while !reader.done() {
+        let mut field = previous;
+        if repeat > 0 {
+            repeat -= 1;
+        } else {
+            let mut at = 0;
+            while at < FIELD_BLOCKS {
+                let run = reader.poll(2)?;
+                let change = (run / FIELD_BLOCKS as u32) as i32 - GRAY as i32;
+                let count = (run % FIELD_BLOCKS as u32) as usize + 1;
+                if change == 0 && count == FIELD_BLOCKS {
+                    repeat = reader.poll(1)?;
+                }
+                let cells = field.get_mut(at..at + count).ok_or("a field runs past its end")?;
+                for cell in cells {
+                    let code = *cell as i32 + change;
+                    if !(0..=GRAY as i32).contains(&code) {
+                        return Err(format!("bad block in the field of page {}", pages.len() + 1));
+                    }
+                    *cell = code as u8;
+                }
+                at += count;
+            }
+        }
+
+        let action = reader.poll(3)?;
+        let kind = (action % 8) as u8;
+        let rotation = rotation_of(action / 8 % 4);
+        let position = action / 32 % FIELD_BLOCKS as u32;
+        let flags = action / 32 / FIELD_BLOCKS as u32;
+        let (rise, mirror, has_comment, lock) = (flags & 1 != 0, flags & 2 != 0, flags & 8 != 0, flags & 16 == 0);
+        if has_comment {
+            comment = read_comment(&mut reader)?;
+        }
+        let piece = block_shape(kind).map(|shape| Placement::from_position(shape, rotation, position));
+        if piece.is_some_and(|piece| !piece.cells().iter().all(|&(x, y)| in_field(x, y))) {
+            return Err(format!("the piece on page {} is outside the field", pages.len() + 1));
+        }
+
+        let page = Page { field, piece, comment: comment.clone() };
+        previous = if lock { page.locked_field() } else { field };
+        if lock && rise {
+            // The garbage row rises into the field and is left empty
+            previous.copy_within(BOARD_WIDTH.., 0);
+            previous[FIELD_TOP * BOARD_WIDTH..].fill(0);
+        }
+        if lock && mirror {
+            for row in previous[..FIELD_TOP * BOARD_WIDTH].chunks_mut(BOARD_WIDTH) {
+                row.reverse();
+            }
+        }
+        pages.push(page);
+    }
+
+    if pages.is_empty() {
+        return Err("the diagram has no pages".to_string());
+    }
+    Ok(pages)
+}
+
+/// Writes the pages as a v115 diagram. Each page's piece locks before the next page.
+pub fn encode(pages: &[Page]) -> String {
+    let mut values = Vec::new();
+    let mut previous = [0; FIELD_BLOCKS];
+    let mut previous_comment = String::new();
+    // Where the count of unchanged pages following an unchanged field is kept
+    let mut repeat_at: Option<usize> = None;
+    for (i, page) in pages.iter().enumerate() {
+        let runs = field_runs(&previous, &page.field);
+        let unchanged = runs == [(GRAY as u32, FIELD_BLOCKS)];
+        match repeat_at {
+            Some(at) if unchanged && values[at] < 63 => values[at] += 1,
+            _ => {
+                for (change, count) in runs {
+                    push(&mut values, change * FIELD_BLOCKS as u32 + count as u32 - 1, 2);
+                }
+                repeat_at = None;
+                if unchanged {
+                    values.push(0);
+                    repeat_at = Some(values.len() - 1);
+                }
+            }
+        }
+
+        let has_comment = page.comment != previous_comment;
+        let (kind, rotation, position) = page
+            .piece
+            .and_then(|piece| Some((block_code(piece.shape) as u32, rotation_code(piece.rotation), piece.position()?)))
+            .unwrap_or((0, 0, 0));
+        // The first page asks for guideline colors; every piece locks
+        let flags = (has_comment as u32) << 3 | ((i == 0) as u32) << 2;
+        let action = ((flags * FIELD_BLOCKS as u32 + position) * 4 + rotation) * 8 + kind;
+        push(&mut values, action, 3);
+        if has_comment {
+            write_comment(&mut values, &page.comment);
+        }
+
+        previous = page.locked_field();
+        previous_comment.clone_from(&page.comment);
+    }
+
+    let data: String = values.iter().map(|&value| ENCODE_TABLE[value as usize] as char).collect();
+    // Fumen breaks long data with '?' so it wraps: after 42 characters, then every 47
+    let mut text = "v115@".to_string();
+    if data.len() <= 42 {
+        text.push_str(&data);
+        return text;
+    }
+    text.push_str(&data[..42]);
+    for chunk in data.as_bytes()[42..].chunks(47) {
+        text.push('?');
+        text.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+    }
+    text
+}
+
+/// The field's changes from `previous` as runs of (change + 8, length)
+fn field_runs(previous: &Field, field: &Field) -> Vec<(u32, usize)> {
+    let mut runs: Vec<(u32, usize)> = Vec::new();
+    for (&before, &after) in previous.iter().zip(field) {
+        let change = (after as i32 - before as i32 + GRAY as i32) as u32;
+        match runs.last_mut() {
+            Some((last, count)) if *last == change => *count += 1,
+            _ => runs.push((change, 1)),
+        }
+    }
+    runs
+}
+
+fn read_comment(reader: &mut Reader) -> Result<String, String> {
+    let length = reader.poll(2)? as usize;
+    let mut escaped = String::with_capacity(length);
+    for _ in 0..length.div_ceil(4) {
+        let mut value = reader.poll(5)?;
+        for _ in 0..4 {
+            let c = COMMENT_TABLE.get((value % COMMENT_BASE) as usize).map_or('?', |&c| c as char);
+            escaped.push(c);
+            value /= COMMENT_BASE;
+        }
+    }
+    escaped.truncate(length);
+    Ok(unescape(&escaped))
+}
+
+fn write_comment(values: &mut Vec<u32>, comment: &str) {
+    let mut escaped = escape(comment);
+    escaped.truncate(4095);
+    push(values, escaped.len() as u32, 2);
+    for chunk in escaped.as_bytes().chunks(4) {
+        let value = chunk.iter().rev().fold(0, |value, &c| {
+            let digit = COMMENT_TABLE.iter().position(|&table| table == c).unwrap_or(0) as u32;
+            value * COMMENT_BASE + digit
+        });
+        push(values, value, 5);
+    }
+}
+
+/// Comments are stored as JavaScript's `escape` writes them
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for unit in text.encode_utf16() {
+        match char::from_u32(unit as u32) {
+            Some(c) if c.is_ascii_alphanumeric() || "@*_+-./".contains(c) => escaped.push(c),
+            _ if unit < 256 => { let _ = write!(escaped, "%{unit:02X}"); }
+            _ => { let _ = write!(escaped, "%u{unit:04X}"); }
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut units = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let hex = |digits: &str| u16::from_str_radix(digits, 16).ok();
+        if let Some(unit) = rest.strip_prefix("%u").and_then(|tail| tail.get(..4)).and_then(hex) {
+            units.push(unit);
+            rest = &rest[6..];
+        } else if let Some(unit) = rest.strip_prefix('%').and_then(|tail| tail.get(..2)).and_then(hex) {
+            units.push(unit);
+            rest = &rest[3..];
+        } else {
+            units.extend(c.encode_utf16(&mut [0; 2]).iter());
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+/// The pieces in a quiz comment, `#Q=[hold](current)queue`: the hold, then the current piece
+/// and the queue
+pub fn quiz(comment: &str) -> Option<(Option<TetrominoShape>, Vec<TetrominoShape>)> {
+    let rest = comment.trim().strip_prefix("#Q=[")?;
+    let (hold, rest) = rest.split_once(']')?;
+    let (current, queue) = rest.strip_prefix('(')?.split_once(')')?;
+    let hold = match hold.trim().chars().next() {
+        Some(c) => Some(TetrominoShape::from_char(c)?),
+        None => None,
+    };
+    let pieces = current
+        .trim()
+        .chars()
+        .chain(queue.chars().take_while(|c| c.is_ascii_alphabetic()))
+        .map(TetrominoShape::from_char)
+        .collect::<Option<Vec<_>>>()?;
+    Some((hold, pieces))
+}
+
+pub fn quiz_comment(hold: Option<TetrominoShape>, pieces: &[TetrominoShape]) -> String {
+    let letters = |shapes: &[TetrominoShape]| shapes.iter().map(|shape| shape.letter()).collect::<String>();
+    let hold = hold.map(|shape| shape.letter().to_string()).unwrap_or_default();
+    let (current, queue) = pieces.split_at(pieces.len().min(1));
+    format!("#Q=[{hold}]({}){}", letters(current), letters(queue))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) {
+        let pages = decode(text).unwrap();
+        assert_eq!(encode(&pages), text);
+    }
+
+    #[test]
+    fn empty_page_round_trips() {
+        round_trip("v115@vhAAgH");
+        let pages = decode("v115@vhAAgH").unwrap();
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].board().unwrap().is_empty());
+    }
+
+    #[test]
+    fn data_at_the_first_break_round_trips() {
+        // 41 data characters
+        round_trip("v115@ShA8AeA8AeA8AeA8BeA8AeA8AeA8AeA8AeA8KeAgH");
+
+        // Stacks of scattered blocks, a colored block or not beside the last, and short comments
+        // land the data on either side of the break
+        let mut lengths = Vec::new();
+        for blocks in 1..BOARD_WIDTH {
+            for colored in [false, true] {
+                let mut board = Board::new();
+                for x in 0..blocks {
+                    board.grid[BOARD_HEIGHT - 1 - x % 2][x] = GARBAGE_COLOR;
+                }
+                if colored {
+                    board.grid[BOARD_HEIGHT - 1 - (blocks - 1) % 2][blocks] = TetrominoShape::T.color_id();
+                }
+                for width in 0..5 {
+                    let mut pages = vec![Page::new(&board)];
+                    pages[0].comment = "x".repeat(width);
+                    let text = encode(&pages);
+                    assert_eq!(decode(&text).unwrap(), pages);
+                    lengths.push(text.len() - "v115@".len() - text.matches('?').count());
+                }
+            }
+        }
+        assert!([41, 42, 43].iter().all(|length| lengths.contains(length)));
+    }
+
+    #[test]
+    fn multi_page_diagram_round_trips() {
+        let shapes = [TetrominoShape::I, TetrominoShape::O, TetrominoShape::T, TetrominoShape::L];
+        let mut pages = vec![Page::new(&Board::new())];
+        for (i, &shape) in shapes.iter().enumerate() {
+            let page = pages.last_mut().unwrap();
+            page.piece = Some(Placement { shape, rotation: i as u8, x: 2 * i as i32 + 1, y: 1 });
+            page.comment = format!("page {}", i + 1);
+            let next = page.next();
+            pages.push(next);
+        }
+        let text = encode(&pages);
+        assert!(text.len() > 47 && text.contains('?'));
+        assert_eq!(decode(&text).unwrap(), pages);
+        round_trip(&text);
+    }
+}
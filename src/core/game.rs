@@ -0,0 +1,554 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::time::{Clock, RiseTimer};
+use super::{
+    Board, Piece, Rules, TSpin, BagRandomizer, SeededRng, GameMode, MasterProgress,
+    AttackTracker, GarbageQueue, Layout, board::BOARD_WIDTH, history::History, tetromino::TetrominoShape,
+};
+
+/// Rows emptied from the top of the well when Zen mode tops out
+const ZEN_CLEAR_ROWS: usize = 10;
+
+/// Lock delay outside of Master mode
+const LOCK_DELAY: f64 = 0.5;
+
+/// Length of one engine step. Games stepped at this fixed rate replay exactly from their
+/// seeds and input log.
+pub const FRAME: f64 = 1.0 / 60.0;
+
+/// Player actions, decoupled from the keys that trigger them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateCw,
+    RotateCcw,
+    Hold,
+    /// Takes back the last placement, in modes that allow it
+    Undo,
+    Redo,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::RotateCw,
+        Action::RotateCcw,
+        Action::Hold,
+        Action::Undo,
+        Action::Redo,
+    ];
+}
+
+/// Things that happened during a step, for the UI (animations, stats) and the opponent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A piece is about to lock; `blocks` holds the top-left cell of each of its blocks
+    Placed { shape: TetrominoShape, blocks: [(i32, i32); 4] },
+    /// A piece locked, clearing `cleared` lines
+    Locked { cleared: u32, t_spin: TSpin },
+    /// Attack left over after countering incoming garbage
+    Attack(u32),
+    /// Zen mode emptied the well instead of topping out
+    BoardReset,
+}
+
+/// Why a game stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEnd {
+    ToppedOut,
+    /// Master mode reached level 999
+    Completed,
+    /// A fixed sequence of pieces ran out
+    OutOfPieces,
+}
+
+/// Fixed piece sequence, used by puzzles instead of the randomizer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PieceSequence {
+    /// Pieces dealt after the next piece
+    pub queue: VecDeque<TetrominoShape>,
+    /// False once the next piece slot has been used up
+    pub has_next: bool,
+    /// Whether the randomizer takes over once the queue is used up, instead of the game ending
+    #[serde(default)]
+    pub endless: bool,
+}
+
+/// One player's game: the well, the pieces and every rule of the selected mode.
+/// The app feeds it actions and time and reacts to its events.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Game {
+    pub mode: GameMode,
+    pub board: Board,
+    pub current_piece: Piece,
+    pub next_piece: Piece,
+    pub hold_piece: Option<Piece>,
+    pub can_hold: bool,
+    pub clock: Clock,
+    pub rng: BagRandomizer,
+    /// Garbage holes come from their own generator so seeded games stay reproducible
+    pub garbage_rng: SeededRng,
+    pub score: u32,
+    pub level: u32,
+    pub lines: u32,
+    /// Pieces locked so far
+    pub pieces: u32,
+    pub lock_timer: Option<f64>,
+    /// Whether the last successful move of the current piece was a rotation (for T-spins)
+    pub last_move_rotation: bool,
+    /// Spawn delay (ARE) left before the next piece appears
+    pub are_timer: Option<f64>,
+    pub rise_timer: RiseTimer,
+    /// Longest chain of a Cascade game
+    pub best_chain: u32,
+    pub master: MasterProgress,
+    /// Garbage waiting to enter the well
+    pub garbage: GarbageQueue,
+    pub attack: AttackTracker,
+    /// Attack left over after countering incoming garbage
+    pub lines_sent: u32,
+    pub sequence: Option<PieceSequence>,
+    /// Set once placement hints were shown; such runs are kept apart from normal records
+    pub assisted: bool,
+    /// Steps taken by `update`
+    pub frame: u64,
+    /// Every input that reached a piece in play, with the frame it came in on
+    pub inputs: Vec<(u64, Action)>,
+    pub history: History,
+    /// Set once a placement was undone; like assisted runs, such runs are flagged
+    pub used_undo: bool,
+    pub end: Option<GameEnd>,
+    /// Consumed by the app every frame, so never worth saving
+    #[serde(skip)]
+    events: Vec<GameEvent>,
+}
+
+impl Game {
+    pub fn new(mode: GameMode, rng: BagRandomizer, garbage_rng: SeededRng) -> Self {
+        let mut game = Self {
+            mode,
+            board: Board::new(),
+            current_piece: Piece::new(TetrominoShape::I),
+            next_piece: Piece::new(TetrominoShape::I),
+            hold_piece: None,
+            can_hold: true,
+            clock: Clock::new(0),
+            rng,
+            garbage_rng,
+            score: 0,
+            level: 0,
+            lines: 0,
+            pieces: 0,
+            lock_timer: None,
+            last_move_rotation: false,
+            are_timer: None,
+            rise_timer: RiseTimer::new(),
+            best_chain: 0,
+            master: MasterProgress::new(),
+            garbage: GarbageQueue::default(),
+            attack: AttackTracker::default(),
+            lines_sent: 0,
+            sequence: None,
+            assisted: false,
+            frame: 0,
+            inputs: Vec::new(),
+            history: History::default(),
+            used_undo: false,
+            end: None,
+            events: Vec::new(),
+        };
+        let first = game.rng.next();
+        game.current_piece = game.spawn_piece(first);
+        game.next_piece = Piece::new(game.rng.next());
+
+        if mode == GameMode::Master {
+            game.clock.set_interval(Rules::master_gravity_interval(0));
+        }
+        game
+    }
+
+    /// A game on a set board that deals `sequence` in order and ends when it runs out
+    pub fn with_sequence(mode: GameMode, board: Board, sequence: Vec<TetrominoShape>) -> Self {
+        let mut game = Self::new(mode, BagRandomizer::new(), SeededRng::from_entropy());
+        let mut queue: VecDeque<_> = sequence.into_iter().collect();
+        if let Some(first) = queue.pop_front() {
+            game.current_piece = Piece::new(first);
+        }
+        let next = queue.pop_front();
+        if let Some(shape) = next {
+            game.next_piece = Piece::new(shape);
+        }
+        game.board = board;
+        game.sequence = Some(PieceSequence { queue, has_next: next.is_some(), endless: false });
+        game
+    }
+
+    /// A game from a board editor position: the layout's pieces are dealt first, then `rng`
+    /// takes over
+    pub fn from_layout(mode: GameMode, layout: &Layout, rng: BagRandomizer, garbage_rng: SeededRng) -> Result<Self, String> {
+        let board = layout.build_board()?;
+        let mut queue: VecDeque<_> = layout.sequence()?.into();
+        let mut game = Self::new(mode, rng, garbage_rng);
+        if let Some(first) = queue.pop_front() {
+            game.current_piece = game.spawn_piece(first);
+        }
+        if let Some(next) = queue.pop_front() {
+            game.next_piece = Piece::new(next);
+        }
+        game.hold_piece = layout.hold.map(Piece::new);
+        game.board = board;
+        game.sequence = Some(PieceSequence { queue, has_next: true, endless: true });
+        Ok(game)
+    }
+
+    /// Events since the last call, oldest first
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Whether a piece is in play (not during the spawn delay or after the game ended)
+    pub fn piece_active(&self) -> bool {
+        self.are_timer.is_none() && self.end.is_none()
+    }
+
+    /// Whether the next piece slot shows a piece
+    pub fn has_next(&self) -> bool {
+        self.sequence.as_ref().is_none_or(|seq| seq.has_next)
+    }
+
+    /// Pieces not yet dealt from a fixed sequence, including the one in the next slot
+    pub fn pieces_left(&self) -> Option<usize> {
+        self.sequence
+            .as_ref()
+            .filter(|seq| !seq.endless)
+            .map(|seq| seq.queue.len() + seq.has_next as usize)
+    }
+
+    /// Queues garbage sent by an opponent
+    pub fn receive_garbage(&mut self, lines: u32) {
+        self.garbage.push(lines, &mut self.garbage_rng);
+    }
+
+    pub fn apply(&mut self, action: Action) {
+        if !self.piece_active() {
+            return;
+        }
+        let undo_action = matches!(action, Action::Undo | Action::Redo);
+        if undo_action && !self.mode.allows_undo() {
+            return;
+        }
+        self.inputs.push((self.frame, action));
+        if self.mode.allows_undo() && !undo_action {
+            self.with_history(|history, game| history.before_input(game));
+        }
+        match action {
+            Action::MoveLeft => self.shift(-1),
+            Action::MoveRight => self.shift(1),
+            Action::SoftDrop => self.soft_drop(),
+            Action::HardDrop => self.hard_drop(),
+            Action::RotateCw => self.rotate(true),
+            Action::RotateCcw => self.rotate(false),
+            Action::Hold => self.hold(),
+            Action::Undo => {
+                if let Some(snapshot) = self.with_history(|history, game| history.undo(game)) {
+                    snapshot.restore(self);
+                    self.used_undo = true;
+                }
+            }
+            Action::Redo => {
+                if let Some(snapshot) = self.with_history(|history, game| history.redo(game)) {
+                    snapshot.restore(self);
+                }
+            }
+        }
+    }
+
+    /// Lends the undo history out alongside the rest of the game
+    fn with_history<T>(&mut self, f: impl FnOnce(&mut History, &Game) -> T) -> T {
+        let mut history = std::mem::take(&mut self.history);
+        let result = f(&mut history, self);
+        self.history = history;
+        result
+    }
+
+    fn finish(&mut self, end: GameEnd) {
+        self.lock_timer = None;
+        self.end = Some(end);
+    }
+
+    pub fn spawn_next_piece(&mut self) {
+        if let Some(seq) = &mut self.sequence {
+            if seq.has_next {
+                self.current_piece = Piece::new(self.next_piece.shape);
+                match seq.queue.pop_front() {
+                    Some(shape) => self.next_piece = Piece::new(shape),
+                    None if seq.endless => self.next_piece = Piece::new(self.rng.next()),
+                    None => seq.has_next = false,
+                }
+            } else if let Some(held) = self.hold_piece.take() {
+                // The sequence is used up but the held piece can still be played
+                self.current_piece = Piece::new(held.shape);
+            } else {
+                self.finish(GameEnd::OutOfPieces);
+                return;
+            }
+        } else {
+            self.current_piece = self.spawn_piece(self.next_piece.shape);
+            self.next_piece = Piece::new(self.rng.next());
+        }
+        self.can_hold = true;
+        self.last_move_rotation = false;
+
+        if self.mode == GameMode::Master {
+            self.master.on_spawn();
+            self.level = self.master.level();
+            self.clock.set_interval(Rules::master_gravity_interval(self.level));
+        }
+
+        if !self.board.can_place(&self.current_piece) {
+            if self.mode == GameMode::Zen {
+                self.zen_top_out();
+            } else {
+                self.finish(GameEnd::ToppedOut);
+            }
+        }
+    }
+
+    /// Zen mode never ends: clear the upper half of the well, or all of it if that is not enough
+    fn zen_top_out(&mut self) {
+        self.board.clear_top(ZEN_CLEAR_ROWS);
+        if !self.board.can_place(&self.current_piece) {
+            self.board = Board::new();
+        }
+        self.events.push(GameEvent::BoardReset);
+    }
+
+    /// A fresh piece at the spawn position, enlarged in Big mode
+    pub fn spawn_piece(&self, shape: TetrominoShape) -> Piece {
+        if self.mode == GameMode::Big {
+            Piece::big(shape)
+        } else {
+            Piece::new(shape)
+        }
+    }
+
+    fn hold(&mut self) {
+        if !self.can_hold {
+            return;
+        }
+        self.can_hold = false;
+
+        if let Some(held) = self.hold_piece.take() {
+            let current_shape = self.current_piece.shape;
+            self.current_piece = self.spawn_piece(held.shape);
+            self.hold_piece = Some(Piece::new(current_shape));
+        } else {
+            self.hold_piece = Some(Piece::new(self.current_piece.shape));
+            self.spawn_next_piece();
+        }
+    }
+
+    fn lock_piece(&mut self) {
+        if self.mode.allows_undo() {
+            self.with_history(|history, game| history.on_lock(game));
+        }
+        let t_spin = if self.last_move_rotation {
+            Rules::detect_t_spin(&self.board, &self.current_piece)
+        } else {
+            TSpin::None
+        };
+
+        let blocks = self.current_piece.scaled_blocks();
+        self.events.push(GameEvent::Placed {
+            shape: self.current_piece.shape,
+            blocks: std::array::from_fn(|i| blocks[i]),
+        });
+        self.board.lock_piece(&self.current_piece);
+        self.pieces += 1;
+        let score_level = if self.mode == GameMode::Master { self.level / 100 } else { self.level };
+        let (cleared, points) = if self.mode == GameMode::Cascade {
+            let chain = self.board.cascade();
+            self.best_chain = self.best_chain.max(chain.len() as u32);
+            (chain.iter().sum(), Rules::score_for_chain(&chain, score_level))
+        } else {
+            // Big pieces clear rows in pairs, which count as one line
+            let cleared = self.board.clear_lines() / self.current_piece.scale as u32;
+            (cleared, Rules::score_for_lines(cleared, score_level))
+        };
+
+        self.lines += cleared;
+        self.score += points;
+        self.update_level(cleared);
+        self.events.push(GameEvent::Locked { cleared, t_spin });
+        self.lock_timer = None;
+
+        // Clears counter incoming garbage; a lock that clears nothing lets ready garbage in
+        let perfect_clear = cleared > 0 && self.board.is_empty();
+        let attack = self.attack.on_lock(cleared, t_spin, perfect_clear);
+        let sent = self.garbage.cancel(attack);
+        if sent > 0 {
+            self.lines_sent += sent;
+            self.events.push(GameEvent::Attack(sent));
+        }
+        if cleared == 0 && !self.garbage.insert_ready(&mut self.board) {
+            self.finish(GameEnd::ToppedOut);
+            return;
+        }
+
+        if self.mode == GameMode::Master {
+            if self.master.is_complete() {
+                self.finish(GameEnd::Completed);
+            } else {
+                self.are_timer = Some(Rules::master_timings(self.level).are);
+            }
+        } else {
+            self.spawn_next_piece();
+        }
+    }
+
+    /// Recomputes the level and gravity after a piece locks
+    fn update_level(&mut self, cleared: u32) {
+        match self.mode {
+            GameMode::Master => {
+                self.master.on_lock(cleared);
+                self.level = self.master.level();
+                self.clock.set_interval(Rules::master_gravity_interval(self.level));
+            }
+            // Zen, Practice and Sandbox keep the starting gravity forever
+            GameMode::Zen | GameMode::Practice | GameMode::Sandbox => self.level = self.lines / 10,
+            _ => {
+                self.level = self.lines / 10;
+                self.clock.set_level(self.level);
+            }
+        }
+    }
+
+    fn lock_delay(&self) -> f64 {
+        if self.mode == GameMode::Master {
+            Rules::master_timings(self.level).lock_delay
+        } else {
+            LOCK_DELAY
+        }
+    }
+
+    /// Survival mode: push a garbage row with a random hole in from the bottom
+    fn raise_garbage(&mut self) {
+        let hole = self.garbage_rng.gen_range(0..BOARD_WIDTH);
+        if !self.board.insert_rows(&[Board::garbage_row(hole)]) {
+            self.finish(GameEnd::ToppedOut);
+            return;
+        }
+
+        // Keep the falling piece above the risen stack
+        while !self.board.can_place(&self.current_piece) {
+            self.current_piece.y -= 1;
+        }
+        self.lock_timer = None;
+    }
+
+    fn shift(&mut self, dx: i32) {
+        let dx = dx * self.current_piece.scale;
+        if self.board.can_move(&self.current_piece, dx, 0) {
+            self.current_piece.x += dx;
+            self.last_move_rotation = false;
+        }
+    }
+
+    fn rotate(&mut self, clockwise: bool) {
+        let rotated = if clockwise {
+            self.current_piece.rotated_cw()
+        } else {
+            self.current_piece.rotated_ccw()
+        };
+        if let Some(kicked) = Rules::try_wall_kick(&self.board, &rotated) {
+            self.current_piece = kicked;
+            self.last_move_rotation = true;
+        }
+    }
+
+    fn hard_drop(&mut self) {
+        let cells_dropped = self.board.drop_distance(&self.current_piece);
+        self.current_piece.y += cells_dropped as i32;
+        if cells_dropped > 0 {
+            self.last_move_rotation = false;
+        }
+        self.score += (cells_dropped as u32) * 2;
+        self.lock_piece();
+    }
+
+    fn soft_drop(&mut self) {
+        let step = self.current_piece.scale;
+        if self.board.can_move(&self.current_piece, 0, step) {
+            self.current_piece.y += step;
+            self.score += 1;
+            self.last_move_rotation = false;
+        }
+    }
+
+    /// Advances timers, gravity and lock delay by `dt` seconds
+    pub fn update(&mut self, dt: f64) {
+        if self.end.is_some() {
+            return;
+        }
+        self.frame += 1;
+        self.board.advance_time(dt);
+        if self.mode == GameMode::Master {
+            self.master.tick(dt);
+        }
+
+        self.garbage.tick(dt);
+
+        // Survival: rising garbage
+        if self.mode == GameMode::Survival && self.rise_timer.tick(dt) {
+            self.raise_garbage();
+            if self.end.is_some() {
+                return;
+            }
+        }
+
+        // Spawn delay: no piece is in play until it runs out
+        if let Some(timer) = &mut self.are_timer {
+            *timer -= dt;
+            if *timer <= 0.0 {
+                self.are_timer = None;
+                self.spawn_next_piece();
+            }
+            return;
+        }
+
+        // Gravity (several rows per frame at high speeds)
+        let step = self.current_piece.scale;
+        for _ in 0..self.clock.ticks(dt) {
+            if !self.board.can_move(&self.current_piece, 0, step) {
+                break;
+            }
+            self.current_piece.y += step;
+            self.lock_timer = None;
+            self.last_move_rotation = false;
+        }
+
+        // Lock delay (processed every frame, not just on gravity ticks)
+        if !self.board.can_move(&self.current_piece, 0, step) {
+            // Piece is on the ground
+            if let Some(timer) = &mut self.lock_timer {
+                *timer -= dt;
+                if *timer <= 0.0 {
+                    self.lock_piece();
+                }
+            } else {
+                self.lock_timer = Some(self.lock_delay());
+            }
+        } else {
+            // Piece is in the air, reset lock timer
+            self.lock_timer = None;
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use super::{Board, TSpin, board::BOARD_WIDTH};
+
+/// Seconds incoming garbage waits in the queue before it can enter the well
+pub const GARBAGE_DELAY: f64 = 1.0;
+
+/// Most garbage rows inserted after a single piece locks
+pub const GARBAGE_CAP: u32 = 8;
+
+/// Chance that a new attack moves the hole instead of lining up with the previous one
+const HOLE_CHANGE_CHANCE: f64 = 0.3;
+
+/// Extra lines for consecutive clears, indexed by combo count (capped at the last entry)
+const COMBO_TABLE: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+
+/// Lines sent by a perfect clear, on top of the clear itself
+const PERFECT_CLEAR_BONUS: u32 = 10;
+
+/// Lines sent for a clear, before combo, back-to-back and perfect clear bonuses
+pub fn base_attack(lines: u32, t_spin: TSpin) -> u32 {
+    match (t_spin, lines) {
+        (_, 0) => 0,
+        (TSpin::Full, lines) => lines * 2,
+        (TSpin::Mini, lines) => lines - 1,
+        (TSpin::None, 4) => 4,
+        (TSpin::None, lines) => lines - 1,
+    }
+}
+
+/// Tracks combo and back-to-back state between locks and turns clears into attack
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttackTracker {
+    /// Consecutive clearing locks, None once a lock clears nothing
+    combo: Option<u32>,
+    /// Whether the last clear was a tetris or a T-spin
+    back_to_back: bool,
+}
+
+impl AttackTracker {
+    /// Lines of garbage sent by a lock
+    pub fn on_lock(&mut self, lines: u32, t_spin: TSpin, perfect_clear: bool) -> u32 {
+        if lines == 0 {
+            self.combo = None;
+            return 0;
+        }
+
+        let combo = self.combo.map_or(0, |c| c + 1);
+        self.combo = Some(combo);
+
+        let difficult = lines == 4 || t_spin != TSpin::None;
+        let mut attack = base_attack(lines, t_spin);
+        attack += COMBO_TABLE[(combo as usize).min(COMBO_TABLE.len() - 1)];
+        if difficult && self.back_to_back {
+            attack += 1;
+        }
+        if perfect_clear {
+            attack += PERFECT_CLEAR_BONUS;
+        }
+        self.back_to_back = difficult;
+        attack
+    }
+
+    pub fn combo(&self) -> u32 {
+        self.combo.unwrap_or(0)
+    }
+
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+}
+
+/// One attack waiting to enter the well
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncomingGarbage {
+    pub lines: u32,
+    /// Column left open in every row of this attack
+    pub hole: usize,
+    /// Seconds until the attack can be inserted
+    pub delay: f64,
+}
+
+/// Garbage received but not yet in the well. Clears first cancel queued lines (oldest
+/// first) and only what is left over is sent on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GarbageQueue {
+    pending: VecDeque<IncomingGarbage>,
+    last_hole: Option<usize>,
+}
+
+impl GarbageQueue {
+    /// Queues an attack. Its hole lines up with the previous attack unless it is moved at random.
+    pub fn push(&mut self, lines: u32, rng: &mut impl RngCore) {
+        if lines == 0 {
+            return;
+        }
+        let hole = match self.last_hole {
+            Some(last) if !rng.gen_bool(HOLE_CHANGE_CHANCE) => last,
+            Some(last) => (last + rng.gen_range(1..BOARD_WIDTH)) % BOARD_WIDTH,
+            None => rng.gen_range(0..BOARD_WIDTH),
+        };
+        self.last_hole = Some(hole);
+        self.pending.push_back(IncomingGarbage { lines, hole, delay: GARBAGE_DELAY });
+    }
+
+    pub fn tick(&mut self, dt: f64) {
+        for garbage in &mut self.pending {
+            garbage.delay = (garbage.delay - dt).max(0.0);
+        }
+    }
+
+    /// Counters queued garbage with an attack, returning the part of the attack left to send
+    pub fn cancel(&mut self, mut attack: u32) -> u32 {
+        while attack > 0 {
+            let Some(front) = self.pending.front_mut() else {
+                break;
+            };
+            let countered = attack.min(front.lines);
+            front.lines -= countered;
+            attack -= countered;
+            if front.lines == 0 {
+                self.pending.pop_front();
+            }
+        }
+        attack
+    }
+
+    /// Moves garbage whose delay has run out into the well, up to `GARBAGE_CAP` rows.
+    /// Returns false if the stack was pushed out of the top.
+    pub fn insert_ready(&mut self, board: &mut Board) -> bool {
+        let mut budget = GARBAGE_CAP;
+        let mut fits = true;
+        while budget > 0 {
+            let Some(front) = self.pending.front_mut() else {
+                break;
+            };
+            if front.delay > 0.0 {
+                break;
+            }
+            let taken = budget.min(front.lines);
+            fits &= board.insert_garbage(taken as usize, front.hole);
+            front.lines -= taken;
+            budget -= taken;
+            if front.lines == 0 {
+                self.pending.pop_front();
+            }
+        }
+        fits
+    }
+
+    /// Total queued lines
+    pub fn total(&self) -> u32 {
+        self.pending.iter().map(|g| g.lines).sum()
+    }
+
+    /// Queued lines that can enter the well on the next lock
+    pub fn ready(&self) -> u32 {
+        self.pending.iter().filter(|g| g.delay <= 0.0).map(|g| g.lines).sum()
+    }
+}
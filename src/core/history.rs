@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use crate::time::Clock;
+use super::{
+    AttackTracker, BagRandomizer, Board, Game, GarbageQueue, Piece, SeededRng,
+    game::PieceSequence, tetromino::TetrominoShape,
+};
+
+/// Everything a placement changes, taken while a piece is waiting at the top of the well
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    board: Board,
+    current: TetrominoShape,
+    next_piece: Piece,
+    hold_piece: Option<Piece>,
+    can_hold: bool,
+    rng: BagRandomizer,
+    garbage_rng: SeededRng,
+    sequence: Option<PieceSequence>,
+    clock: Clock,
+    score: u32,
+    level: u32,
+    lines: u32,
+    pieces: u32,
+    best_chain: u32,
+    garbage: GarbageQueue,
+    attack: AttackTracker,
+    lines_sent: u32,
+}
+
+impl Snapshot {
+    pub fn take(game: &Game) -> Self {
+        Self {
+            board: game.board.clone(),
+            current: game.current_piece.shape,
+            next_piece: game.next_piece.clone(),
+            hold_piece: game.hold_piece.clone(),
+            can_hold: game.can_hold,
+            rng: game.rng.clone(),
+            garbage_rng: game.garbage_rng.clone(),
+            sequence: game.sequence.clone(),
+            clock: game.clock.clone(),
+            score: game.score,
+            level: game.level,
+            lines: game.lines,
+            pieces: game.pieces,
+            best_chain: game.best_chain,
+            garbage: game.garbage.clone(),
+            attack: game.attack.clone(),
+            lines_sent: game.lines_sent,
+        }
+    }
+
+    /// Puts the game back as it was, with the piece of the moment back at its spawn position
+    pub fn restore(self, game: &mut Game) {
+        game.board = self.board;
+        game.current_piece = game.spawn_piece(self.current);
+        game.next_piece = self.next_piece;
+        game.hold_piece = self.hold_piece;
+        game.can_hold = self.can_hold;
+        game.rng = self.rng;
+        game.garbage_rng = self.garbage_rng;
+        game.sequence = self.sequence;
+        game.clock = self.clock;
+        game.score = self.score;
+        game.level = self.level;
+        game.lines = self.lines;
+        game.pieces = self.pieces;
+        game.best_chain = self.best_chain;
+        game.garbage = self.garbage;
+        game.attack = self.attack;
+        game.lines_sent = self.lines_sent;
+        game.lock_timer = None;
+        game.last_move_rotation = false;
+    }
+}
+
+/// Undo and redo stacks of placements, for the modes that allow taking moves back
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// The current piece's state before the first input reached it, so an undo also takes
+    /// back a hold
+    pending: Option<Snapshot>,
+}
+
+impl History {
+    /// Called on each input, before it is applied
+    pub fn before_input(&mut self, game: &Game) {
+        if self.pending.is_none() {
+            self.pending = Some(Snapshot::take(game));
+        }
+    }
+
+    /// Called as a piece locks, before the board changes. A new placement ends any redo.
+    pub fn on_lock(&mut self, game: &Game) {
+        let snapshot = self.pending.take().unwrap_or_else(|| Snapshot::take(game));
+        self.undo.push(snapshot);
+        self.redo.clear();
+    }
+
+    /// The placement to undo, if any; the game as it stands becomes the redo
+    pub fn undo(&mut self, game: &Game) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+        let current = self.pending.take().unwrap_or_else(|| Snapshot::take(game));
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, game: &Game) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        let current = self.pending.take().unwrap_or_else(|| Snapshot::take(game));
+        self.undo.push(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use super::{Board, board::{BOARD_WIDTH, BOARD_HEIGHT, GARBAGE_COLOR}, fumen, tetromino::TetrominoShape};
+
+/// Builds a board from the bottom rows of a stack, listed top to bottom.
+/// `.` is empty, `G` is garbage and piece letters take that piece's color.
+pub fn parse_rows(id: &str, rows: &[String]) -> Result<Board, String> {
+    if rows.len() > BOARD_HEIGHT {
+        return Err(format!("{id}: board has more than {BOARD_HEIGHT} rows"));
+    }
+
+    let mut board = Board::new();
+    let top = BOARD_HEIGHT - rows.len();
+    for (i, row) in rows.iter().enumerate() {
+        let cells: Vec<char> = row.chars().collect();
+        if cells.len() != BOARD_WIDTH {
+            return Err(format!("{id}: row {} is not {BOARD_WIDTH} cells wide", i + 1));
+        }
+        for (x, c) in cells.into_iter().enumerate() {
+            board.grid[top + i][x] = match c {
+                '.' | ' ' => 0,
+                'G' | 'g' | '#' => GARBAGE_COLOR,
+                _ => TetrominoShape::from_char(c)
+                    .map(|shape| shape.color_id())
+                    .ok_or_else(|| format!("{id}: unknown cell '{c}'"))?,
+            };
+        }
+    }
+    Ok(board)
+}
+
+/// The stack in the notation `parse_rows` reads, from its highest filled row down
+pub fn board_rows(board: &Board) -> Vec<String> {
+    let first = board.grid.iter().position(|row| row.iter().any(|&cell| cell != 0)).unwrap_or(BOARD_HEIGHT);
+    board.grid[first..]
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&cell| match cell {
+                    0 => '.',
+                    GARBAGE_COLOR => 'G',
+                    id => TetrominoShape::from_color_id(id).map_or('G', |shape| shape.letter()),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Piece letters, ignoring whitespace
+pub fn parse_pieces(id: &str, text: &str) -> Result<Vec<TetrominoShape>, String> {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| TetrominoShape::from_char(c).ok_or_else(|| format!("{id}: unknown piece '{c}'")))
+        .collect()
+}
+
+/// A position to play from, as made in the board editor: the stack and the pieces around it.
+/// Uses the same notation as puzzles, so a layout can become one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Layout {
+    pub name: String,
+    /// Bottom rows of the stack, listed top to bottom
+    pub board: Vec<String>,
+    /// The current piece, then the queue, as piece letters
+    pub pieces: String,
+    #[serde(default)]
+    pub hold: Option<TetrominoShape>,
+}
+
+impl Layout {
+    pub fn new(name: &str, board: &Board, pieces: &[TetrominoShape], hold: Option<TetrominoShape>) -> Self {
+        Self {
+            name: name.to_string(),
+            board: board_rows(board),
+            pieces: pieces.iter().map(|shape| shape.letter()).collect(),
+            hold,
+        }
+    }
+
+    pub fn build_board(&self) -> Result<Board, String> {
+        parse_rows(&self.name, &self.board)
+    }
+
+    /// The current piece and the queue; at least the current piece is needed
+    pub fn sequence(&self) -> Result<Vec<TetrominoShape>, String> {
+        let sequence = parse_pieces(&self.name, &self.pieces)?;
+        if sequence.is_empty() {
+            return Err(format!("{}: no current piece", self.name));
+        }
+        Ok(sequence)
+    }
+
+    /// Short text form for sharing: `rows|pieces|hold|name`, rows joined by `/` and runs of
+    /// empty cells written as their length, e.g. `8G1/GGGG2GGGG|TI|S|Tuck`
+    pub fn to_code(&self) -> String {
+        let rows: Vec<String> = self
+            .board
+            .iter()
+            .map(|row| {
+                let mut code = String::new();
+                let mut empty = 0;
+                for c in row.chars() {
+                    if c == '.' {
+                        empty += 1;
+                        continue;
+                    }
+                    if empty > 0 {
+                        code.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    code.push(c);
+                }
+                if empty > 0 {
+                    code.push_str(&empty.to_string());
+                }
+                code
+            })
+            .collect();
+        let hold = self.hold.map(|shape| shape.letter().to_string()).unwrap_or_default();
+        let name = self.name.replace('|', " ");
+        format!("{}|{}|{}|{}", rows.join("/"), self.pieces, hold, name)
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, String> {
+        let mut fields = code.trim().splitn(4, '|');
+        let (Some(rows), Some(pieces)) = (fields.next(), fields.next()) else {
+            return Err("not a layout code".to_string());
+        };
+        let hold = fields.next().unwrap_or("").trim();
+        let name = fields.next().unwrap_or("").trim();
+
+        let board = rows
+            .split('/')
+            .filter(|row| !row.is_empty())
+            .map(|row| {
+                let mut cells = String::new();
+                let mut run = String::new();
+                for c in row.chars() {
+                    if c.is_ascii_digit() {
+                        run.push(c);
+                        continue;
+                    }
+                    if !run.is_empty() {
+                        let count: usize = run.parse().map_err(|_| "bad run of empty cells")?;
+                        cells.push_str(&".".repeat(count.min(BOARD_WIDTH)));
+                        run.clear();
+                    }
+                    cells.push(c);
+                }
+                if !run.is_empty() {
+                    let count: usize = run.parse().map_err(|_| "bad run of empty cells")?;
+                    cells.push_str(&".".repeat(count.min(BOARD_WIDTH)));
+                }
+                Ok(cells)
+            })
+            .collect::<Result<Vec<_>, &str>>()?;
+
+        let hold = match hold.chars().next() {
+            Some(c) => Some(TetrominoShape::from_char(c).ok_or_else(|| format!("unknown hold piece '{c}'"))?),
+            None => None,
+        };
+        let layout = Self {
+            name: if name.is_empty() { "Shared layout".to_string() } else { name.to_string() },
+            board,
+            pieces: pieces.trim().to_string(),
+            hold,
+        };
+        layout.build_board()?;
+        layout.sequence()?;
+        Ok(layout)
+    }
+
+    /// Each page of a fumen diagram as a layout. A page's pieces come from its quiz comment
+    /// (`#Q=[hold](current)queue`) if it has one, otherwise from the pieces placed on it and on
+    /// the pages after it.
+    pub fn from_fumen(text: &str) -> Result<Vec<Self>, String> {
+        let pages = fumen::decode(text)?;
+        pages
+            .iter()
+            .enumerate()
+            .map(|(i, page)| {
+                let board = page.board().map_err(|err| format!("page {}: {err}", i + 1))?;
+                let quiz = fumen::quiz(&page.comment);
+                let name = match page.comment.trim() {
+                    comment if quiz.is_none() && !comment.is_empty() => comment.chars().take(40).collect(),
+                    _ if pages.len() > 1 => format!("Fumen page {}", i + 1),
+                    _ => "Fumen".to_string(),
+                };
+                let (hold, pieces) = quiz.unwrap_or_else(|| {
+                    (None, pages[i..].iter().filter_map(|page| page.piece.map(|piece| piece.shape)).collect())
+                });
+                Ok(Self::new(&name, &board, &pieces, hold))
+            })
+            .collect()
+    }
+
+    /// The position as a one-page fumen diagram, its pieces in a quiz comment
+    pub fn to_fumen(&self) -> Result<String, String> {
+        let mut page = fumen::Page::new(&self.build_board()?);
+        let pieces = parse_pieces(&self.name, &self.pieces)?;
+        if !pieces.is_empty() || self.hold.is_some() {
+            page.comment = fumen::quiz_comment(self.hold, &pieces);
+        }
+        Ok(fumen::encode(&[page]))
+    }
+}
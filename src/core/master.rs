@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use super::Rules;
+
+/// Highest level of Master mode; reaching it ends the game
+pub const MASTER_MAX_LEVEL: u32 = 999;
+
+/// Grades from lowest to highest, indexed by internal grade
+const GRADE_NAMES: [&str; 18] = [
+    "9", "8", "7", "6", "5", "4", "3", "2", "1",
+    "S1", "S2", "S3", "S4", "S5", "S6", "S7", "S8", "S9",
+];
+
+/// Grade points per single/double/triple/tetris
+const CLEAR_POINTS: [f64; 4] = [10.0, 20.0, 30.0, 50.0];
+
+/// Grade points needed to advance one internal grade
+const POINTS_PER_GRADE: f64 = 100.0;
+
+/// A section finished faster than this earns a bonus grade
+const COOL_SECTION_TIME: f64 = 60.0;
+
+/// A section finished slower than this cancels one cool bonus
+const REGRET_SECTION_TIME: f64 = 90.0;
+
+/// TGM-style level, grade and section tracking for Master mode
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MasterProgress {
+    level: u32,
+    grade_points: f64,
+    internal_grade: usize,
+    combo: u32,
+    decay_timer: f64,
+    section_timer: f64,
+    section_times: Vec<f64>,
+    elapsed: f64,
+}
+
+impl MasterProgress {
+    pub fn new() -> Self {
+        Self {
+            level: 0,
+            grade_points: 0.0,
+            internal_grade: 0,
+            combo: 0,
+            decay_timer: 0.0,
+            section_timer: 0.0,
+            section_times: Vec::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn section_times(&self) -> &[f64] {
+        &self.section_times
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.level >= MASTER_MAX_LEVEL
+    }
+
+    /// Level the current section ends at (the "stop" shown next to the level)
+    pub fn section_target(&self) -> u32 {
+        ((self.level / 100 + 1) * 100).min(MASTER_MAX_LEVEL)
+    }
+
+    /// Advances the timers; grade points decay slowly while no lines are cleared
+    pub fn tick(&mut self, dt: f64) {
+        self.elapsed += dt;
+        self.section_timer += dt;
+
+        if self.grade_points > 0.0 {
+            self.decay_timer += dt;
+            let interval = Rules::grade_decay_interval(self.internal_grade as u32);
+            while self.decay_timer >= interval {
+                self.decay_timer -= interval;
+                self.grade_points = (self.grade_points - 1.0).max(0.0);
+            }
+        }
+    }
+
+    /// A new piece entered the field. Levels ending in 99 (and 998) need a line clear to pass.
+    pub fn on_spawn(&mut self) {
+        if self.level % 100 != 99 && self.level != MASTER_MAX_LEVEL - 1 {
+            self.advance(1);
+        }
+    }
+
+    pub fn on_lock(&mut self, cleared: u32) {
+        if cleared == 0 {
+            self.combo = 0;
+            return;
+        }
+
+        self.combo += 1;
+        let level_bonus = 1.0 + self.level as f64 / 250.0;
+        let combo_bonus = (1.0 + 0.2 * (self.combo - 1) as f64).min(2.0);
+        self.grade_points += CLEAR_POINTS[cleared.min(4) as usize - 1] * level_bonus * combo_bonus;
+        self.decay_timer = 0.0;
+
+        while self.grade_points >= POINTS_PER_GRADE && self.internal_grade < GRADE_NAMES.len() - 1 {
+            self.grade_points -= POINTS_PER_GRADE;
+            self.internal_grade += 1;
+        }
+
+        self.advance(cleared);
+    }
+
+    fn advance(&mut self, amount: u32) {
+        let previous = self.level;
+        self.level = (self.level + amount).min(MASTER_MAX_LEVEL);
+
+        let crossed_section = self.level / 100 > previous / 100;
+        if crossed_section || (self.is_complete() && previous < MASTER_MAX_LEVEL) {
+            self.section_times.push(self.section_timer);
+            self.section_timer = 0.0;
+        }
+    }
+
+    /// Grade earned from grade points alone
+    pub fn grade(&self) -> &'static str {
+        GRADE_NAMES[self.internal_grade]
+    }
+
+    /// Final grade: fast sections add bonus grades, slow ones take them away,
+    /// and clearing level 999 fast enough in every section awards GM
+    pub fn final_grade(&self) -> &'static str {
+        let cools = self.section_times.iter().filter(|&&t| t <= COOL_SECTION_TIME).count();
+        let regrets = self.section_times.iter().filter(|&&t| t >= REGRET_SECTION_TIME).count();
+        let bonus = cools.saturating_sub(regrets);
+        let grade = (self.internal_grade + bonus).min(GRADE_NAMES.len() - 1);
+
+        if self.is_complete() && grade == GRADE_NAMES.len() - 1 {
+            if regrets == 0 && cools == self.section_times.len() {
+                "GM"
+            } else {
+                "M"
+            }
+        } else {
+            GRADE_NAMES[grade]
+        }
+    }
+}
+
+impl Default for MasterProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,9 +3,27 @@ pub mod piece;
 pub mod tetromino;
 pub mod rules;
 pub mod rng;
+pub mod mode;
+pub mod master;
+pub mod puzzle;
+pub mod daily;
+pub mod garbage;
+pub mod game;
+pub mod ai;
+pub mod history;
+pub mod layout;
+pub mod fumen;
 
 pub use board::Board;
 pub use piece::Piece;
 pub use tetromino::Tetromino;
-pub use rules::Rules;
-pub use rng::BagRandomizer;
+pub use rules::{Rules, TSpin};
+pub use rng::{BagRandomizer, SeededRng};
+pub use mode::GameMode;
+pub use master::MasterProgress;
+pub use puzzle::{Puzzle, PuzzleRun};
+pub use layout::Layout;
+pub use daily::DailyChallenge;
+pub use garbage::{AttackTracker, GarbageQueue};
+pub use game::{Action, Game, GameEvent, GameEnd};
+pub use ai::Bot;
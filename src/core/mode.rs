@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+/// Game modes selectable from the main menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    Marathon,
+    Survival,
+    Zen,
+    Master,
+    Puzzle,
+    Invisible,
+    Fading,
+    Big,
+    Mirror,
+    UpsideDown,
+    Cascade,
+    Practice,
+    /// Played from a position made in the board editor
+    Sandbox,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 13] = [
+        GameMode::Marathon,
+        GameMode::Survival,
+        GameMode::Zen,
+        GameMode::Master,
+        GameMode::Puzzle,
+        GameMode::Invisible,
+        GameMode::Fading,
+        GameMode::Big,
+        GameMode::Mirror,
+        GameMode::UpsideDown,
+        GameMode::Cascade,
+        GameMode::Practice,
+        GameMode::Sandbox,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Marathon => "Marathon",
+            GameMode::Survival => "Survival",
+            GameMode::Zen => "Zen",
+            GameMode::Master => "Master",
+            GameMode::Puzzle => "Puzzle",
+            GameMode::Invisible => "Invisible",
+            GameMode::Fading => "Fading",
+            GameMode::Big => "Big",
+            GameMode::Mirror => "Mirror",
+            GameMode::UpsideDown => "Upside-Down",
+            GameMode::Cascade => "Cascade",
+            GameMode::Practice => "Practice",
+            GameMode::Sandbox => "Sandbox",
+        }
+    }
+
+    /// Whether locked blocks are hidden from view after a while
+    pub fn hides_stack(&self) -> bool {
+        matches!(self, GameMode::Invisible | GameMode::Fading)
+    }
+
+    /// Whether placements can be taken back and redone
+    pub fn allows_undo(&self) -> bool {
+        matches!(self, GameMode::Practice | GameMode::Sandbox)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            GameMode::Marathon => "Classic endless play",
+            GameMode::Survival => "Garbage rises from below, faster and faster",
+            GameMode::Zen => "No top-out, no speed-up, progress is kept between launches",
+            GameMode::Master => "Levels 0–999 up to 20G, graded at the end",
+            GameMode::Puzzle => "Solve set boards with a fixed sequence of pieces",
+            GameMode::Invisible => "Locked blocks vanish at once; line clears flash the stack outline",
+            GameMode::Fading => "Locked blocks fade away after a few seconds",
+            GameMode::Big => "Double-size pieces in a well five blocks wide",
+            GameMode::Mirror => "The board is flipped left to right; S/Z and L/J trade places",
+            GameMode::UpsideDown => "The board is turned over and pieces fall upwards",
+            GameMode::Cascade => "Blocks fall as connected units after a clear, setting off chains",
+            GameMode::Practice => "Steady speed; undo and redo any placement",
+            GameMode::Sandbox => "Paint a board and pieces in the editor, then play from there",
+        }
+    }
+}
@@ -1,12 +1,15 @@
+use serde::{Deserialize, Serialize};
 use super::tetromino::{Tetromino, TetrominoShape};
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Piece {
     pub x: i32,
     pub y: i32,
     pub shape: TetrominoShape,
     pub rotation: usize,
     pub color_id: u8,
+    /// Size of each block in cells (2 in Big mode)
+    pub scale: i32,
 }
 
 impl Piece {
@@ -17,13 +20,37 @@ impl Piece {
             shape,
             rotation: 0,
             color_id: shape.color_id(),
+            scale: 1,
         }
     }
 
+    /// A piece whose blocks are 2×2 cells, spawned on an even column
+    pub fn big(shape: TetrominoShape) -> Self {
+        Self {
+            x: 2,
+            scale: 2,
+            ..Self::new(shape)
+        }
+    }
+
+    /// Board cells covered by the piece
     pub fn blocks(&self) -> Vec<(i32, i32)> {
+        let mut cells = Vec::with_capacity(4 * (self.scale * self.scale) as usize);
+        for (x, y) in self.scaled_blocks() {
+            for sy in 0..self.scale {
+                for sx in 0..self.scale {
+                    cells.push((x + sx, y + sy));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Top-left cell of each of the four (possibly enlarged) blocks
+    pub fn scaled_blocks(&self) -> Vec<(i32, i32)> {
         Tetromino::get(self.shape).rotations[self.rotation]
             .iter()
-            .map(|(dx, dy)| (self.x + dx, self.y + dy))
+            .map(|(dx, dy)| (self.x + dx * self.scale, self.y + dy * self.scale))
             .collect()
     }
 
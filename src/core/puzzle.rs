@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use super::{Board, TSpin, layout, tetromino::TetrominoShape};
+
+/// What has to be achieved by the time the piece sequence runs out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PuzzleGoal {
+    ClearLines { lines: u32 },
+    PerfectClear,
+    TSpins { lines: u32, count: u32 },
+    Tetrises { count: u32 },
+}
+
+impl PuzzleGoal {
+    pub fn description(&self) -> String {
+        match self {
+            PuzzleGoal::ClearLines { lines } => format!("Clear {lines} lines"),
+            PuzzleGoal::PerfectClear => "Perfect clear".to_string(),
+            PuzzleGoal::TSpins { lines, count } => {
+                let kind = match lines {
+                    0 => "T-Spin",
+                    1 => "T-Spin Single",
+                    2 => "T-Spin Double",
+                    _ => "T-Spin Triple",
+                };
+                if *count == 1 { kind.to_string() } else { format!("{kind} ×{count}") }
+            }
+            PuzzleGoal::Tetrises { count } => {
+                if *count == 1 { "Tetris".to_string() } else { format!("Tetris ×{count}") }
+            }
+        }
+    }
+}
+
+/// A puzzle level: a starting stack, a fixed piece sequence and a goal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Bottom rows of the starting stack, listed top to bottom.
+    /// `.` is empty, `G` is garbage and piece letters take that piece's color.
+    pub board: Vec<String>,
+    /// Piece letters in the order they are dealt
+    pub pieces: String,
+    pub goal: PuzzleGoal,
+}
+
+impl Puzzle {
+    pub fn build_board(&self) -> Result<Board, String> {
+        layout::parse_rows(&self.id, &self.board)
+    }
+
+    pub fn sequence(&self) -> Result<Vec<TetrominoShape>, String> {
+        let sequence = layout::parse_pieces(&self.id, &self.pieces)?;
+        if sequence.is_empty() {
+            return Err(format!("{}: empty piece sequence", self.id));
+        }
+        Ok(sequence)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.build_board()?;
+        self.sequence()?;
+        Ok(())
+    }
+}
+
+/// Progress towards a puzzle goal while it is being played
+#[derive(Debug, Clone, Default)]
+pub struct PuzzleRun {
+    pub lines: u32,
+    /// Lines cleared by each T-spin
+    pub t_spins: Vec<u32>,
+    pub tetrises: u32,
+}
+
+impl PuzzleRun {
+    pub fn record_lock(&mut self, cleared: u32, t_spin: TSpin) {
+        self.lines += cleared;
+        if t_spin != TSpin::None {
+            self.t_spins.push(cleared);
+        }
+        if cleared == 4 {
+            self.tetrises += 1;
+        }
+    }
+
+    /// Checks the goal once the sequence has run out
+    pub fn is_solved(&self, goal: &PuzzleGoal, board: &Board) -> bool {
+        match goal {
+            PuzzleGoal::ClearLines { lines } => self.lines >= *lines,
+            PuzzleGoal::PerfectClear => self.lines > 0 && board.is_empty(),
+            PuzzleGoal::TSpins { lines, count } => {
+                self.t_spins.iter().filter(|&&l| l == *lines).count() as u32 >= *count
+            }
+            PuzzleGoal::Tetrises { count } => self.tetrises >= *count,
+        }
+    }
+}
@@ -1,24 +1,82 @@
 use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
 use super::tetromino::TetrominoShape;
 
+/// Small deterministic generator (SplitMix64). Unlike `thread_rng` it can be seeded and
+/// saved, so the same seed always yields the same pieces on every machine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeded from the thread generator, for ordinary games
+    pub fn from_entropy() -> Self {
+        Self::new(rand::thread_rng().gen())
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BagRandomizer {
     bag: Vec<TetrominoShape>,
+    rng: SeededRng,
 }
 
 impl BagRandomizer {
     pub fn new() -> Self {
-        let mut rng = Self { bag: Vec::new() };
-        rng.refill();
-        rng
+        Self::with_rng(SeededRng::from_entropy())
+    }
+
+    /// A bag that deals the same sequence for the same seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(SeededRng::new(seed))
+    }
+
+    fn with_rng(rng: SeededRng) -> Self {
+        let mut bag = Self { bag: Vec::new(), rng };
+        bag.refill();
+        bag
     }
 
     fn refill(&mut self) {
-        use TetrominoShape::*;
-        let mut pieces = vec![I, O, T, L, J, S, Z];
-        pieces.shuffle(&mut rand::thread_rng());
+        let mut pieces = TetrominoShape::ALL.to_vec();
+        pieces.shuffle(&mut self.rng);
         self.bag = pieces;
     }
 
+    /// The bag never runs out, so this isn't an `Iterator`
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> TetrominoShape {
         if self.bag.is_empty() {
             self.refill();
@@ -26,3 +84,9 @@ impl BagRandomizer {
         self.bag.pop().unwrap()
     }
 }
+
+impl Default for BagRandomizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
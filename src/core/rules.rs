@@ -1,4 +1,40 @@
-use super::{Board, Piece};
+use super::{Board, Piece, board::{BOARD_WIDTH, BOARD_HEIGHT}, tetromino::TetrominoShape};
+
+/// Duration of one frame at the 60 Hz reference rate
+const FRAME: f64 = 1.0 / 60.0;
+
+/// Master mode gravity as (starting level, rows per frame × 256); 5120 is 20G
+const MASTER_GRAVITY: [(u32, u32); 30] = [
+    (0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32), (80, 48),
+    (90, 64), (100, 80), (120, 96), (140, 112), (160, 128), (170, 144), (200, 4),
+    (220, 32), (230, 64), (233, 96), (236, 128), (239, 160), (243, 192), (247, 224),
+    (251, 256), (300, 512), (330, 768), (360, 1024), (400, 1280), (420, 1024),
+    (450, 768), (500, 5120),
+];
+
+/// Master mode delays in frames as (starting level, ARE, lock delay)
+const MASTER_TIMINGS: [(u32, u32, u32); 6] = [
+    (0, 25, 30),
+    (500, 18, 28),
+    (600, 14, 24),
+    (700, 12, 20),
+    (800, 10, 18),
+    (900, 8, 15),
+];
+
+/// Spawn and lock delays for a Master mode level, in seconds
+pub struct MasterTimings {
+    pub are: f64,
+    pub lock_delay: f64,
+}
+
+/// Kind of T-spin detected when a T piece locks, ordered by worth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
 
 pub struct Rules;
 
@@ -15,7 +51,16 @@ impl Rules {
         base * (level + 1)
     }
 
-    /// Simple wall kick: try offsets (0,0), (±1,0), (±2,0), (0,-1)
+    /// Cascade scoring: each step of a chain scores its lines, multiplied by its place in the chain
+    pub fn score_for_chain(chain: &[u32], level: u32) -> u32 {
+        chain
+            .iter()
+            .zip(1..)
+            .map(|(&lines, step)| Self::score_for_lines(lines.min(4), level) * step)
+            .sum()
+    }
+
+    /// Simple wall kick: try offsets (0,0), (±1,0), (±2,0), (0,-1), in blocks of the piece's scale
     pub fn try_wall_kick(board: &Board, piece: &Piece) -> Option<Piece> {
         let offsets = [
             (0, 0),
@@ -27,7 +72,7 @@ impl Rules {
         ];
 
         for (dx, dy) in offsets {
-            let kicked = piece.with_offset(dx, dy);
+            let kicked = piece.with_offset(dx * piece.scale, dy * piece.scale);
             if board.can_place(&kicked) {
                 return Some(kicked);
             }
@@ -35,6 +80,36 @@ impl Rules {
         None
     }
 
+    /// Three-corner T-spin check, to be called before the piece is locked and
+    /// only when its last successful move was a rotation
+    pub fn detect_t_spin(board: &Board, piece: &Piece) -> TSpin {
+        if piece.shape != TetrominoShape::T {
+            return TSpin::None;
+        }
+
+        // Corners around the T's center at local (1, 1); the first two face the pointing side
+        let corners = match piece.rotation {
+            0 => [(0, 0), (2, 0), (0, 2), (2, 2)],
+            1 => [(2, 0), (2, 2), (0, 0), (0, 2)],
+            2 => [(0, 2), (2, 2), (0, 0), (2, 0)],
+            _ => [(0, 0), (0, 2), (2, 0), (2, 2)],
+        };
+        let filled = corners.map(|(dx, dy)| {
+            let (x, y) = (piece.x + dx * piece.scale, piece.y + dy * piece.scale);
+            if x < 0 || x >= BOARD_WIDTH as i32 || y >= BOARD_HEIGHT as i32 {
+                true
+            } else {
+                y >= 0 && board.grid[y as usize][x as usize] != 0
+            }
+        });
+
+        match filled.iter().filter(|&&f| f).count() {
+            0..=2 => TSpin::None,
+            _ if filled[0] && filled[1] => TSpin::Full,
+            _ => TSpin::Mini,
+        }
+    }
+
     /// Gravity interval in seconds based on level
     pub fn gravity_interval(level: u32) -> f64 {
         let base_ms = 800.0;
@@ -42,4 +117,41 @@ impl Rules {
         let decay = 0.9_f64.powi(level as i32);
         (base_ms * decay).max(min_ms) / 1000.0
     }
+
+    /// Seconds between garbage rises in Survival mode, shrinking with each rise
+    pub fn rise_interval(rises: u32) -> f64 {
+        let base = 8.0;
+        let min = 1.5;
+        (base * 0.95_f64.powi(rises as i32)).max(min)
+    }
+
+    /// Seconds per row of Master mode gravity; at 20G a piece falls the whole well in one frame
+    pub fn master_gravity_interval(level: u32) -> f64 {
+        let (_, internal) = MASTER_GRAVITY
+            .iter()
+            .rev()
+            .find(|(start, _)| level >= *start)
+            .copied()
+            .unwrap_or(MASTER_GRAVITY[0]);
+        FRAME * 256.0 / internal as f64
+    }
+
+    /// ARE and lock delay shrink at Master mode section thresholds
+    pub fn master_timings(level: u32) -> MasterTimings {
+        let (_, are, lock_delay) = MASTER_TIMINGS
+            .iter()
+            .rev()
+            .find(|(start, _, _)| level >= *start)
+            .copied()
+            .unwrap_or(MASTER_TIMINGS[0]);
+        MasterTimings {
+            are: are as f64 * FRAME,
+            lock_delay: lock_delay as f64 * FRAME,
+        }
+    }
+
+    /// Seconds for one grade point to decay; higher grades decay faster
+    pub fn grade_decay_interval(internal_grade: u32) -> f64 {
+        (2.0 - internal_grade as f64 * 0.08).max(0.5)
+    }
 }
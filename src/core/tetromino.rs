@@ -1,9 +1,21 @@
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum TetrominoShape {
     I, O, T, L, J, S, Z,
 }
 
 impl TetrominoShape {
+    pub const ALL: [TetrominoShape; 7] = [
+        TetrominoShape::I,
+        TetrominoShape::O,
+        TetrominoShape::T,
+        TetrominoShape::L,
+        TetrominoShape::J,
+        TetrominoShape::S,
+        TetrominoShape::Z,
+    ];
+
     pub fn color_id(&self) -> u8 {
         match self {
             TetrominoShape::I => 1,
@@ -15,6 +27,36 @@ impl TetrominoShape {
             TetrominoShape::Z => 7,
         }
     }
+
+    pub fn from_color_id(color_id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|shape| shape.color_id() == color_id)
+    }
+
+    pub fn letter(&self) -> char {
+        match self {
+            TetrominoShape::I => 'I',
+            TetrominoShape::O => 'O',
+            TetrominoShape::T => 'T',
+            TetrominoShape::L => 'L',
+            TetrominoShape::J => 'J',
+            TetrominoShape::S => 'S',
+            TetrominoShape::Z => 'Z',
+        }
+    }
+
+    /// Parses a piece letter (case-insensitive)
+    pub fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'I' => Some(TetrominoShape::I),
+            'O' => Some(TetrominoShape::O),
+            'T' => Some(TetrominoShape::T),
+            'L' => Some(TetrominoShape::L),
+            'J' => Some(TetrominoShape::J),
+            'S' => Some(TetrominoShape::S),
+            'Z' => Some(TetrominoShape::Z),
+            _ => None,
+        }
+    }
 }
 
 pub struct Tetromino {
@@ -64,19 +106,19 @@ static TETROMINO_T: Tetromino = Tetromino {
 
 static TETROMINO_L: Tetromino = Tetromino {
     rotations: [
-        [(0, 0), (0, 1), (1, 1), (2, 1)],
-        [(1, 0), (1, 1), (1, 2), (2, 0)],
-        [(0, 1), (1, 1), (2, 1), (2, 2)],
-        [(1, 0), (0, 2), (1, 1), (1, 2)],
+        [(2, 0), (0, 1), (1, 1), (2, 1)],
+        [(1, 0), (1, 1), (1, 2), (2, 2)],
+        [(0, 1), (1, 1), (2, 1), (0, 2)],
+        [(0, 0), (1, 0), (1, 1), (1, 2)],
     ],
 };
 
 static TETROMINO_J: Tetromino = Tetromino {
     rotations: [
-        [(2, 0), (0, 1), (1, 1), (2, 1)],
-        [(1, 0), (1, 1), (1, 2), (2, 2)],
-        [(0, 1), (1, 1), (2, 1), (0, 2)],
-        [(0, 0), (1, 0), (1, 1), (1, 2)],
+        [(0, 0), (0, 1), (1, 1), (2, 1)],
+        [(1, 0), (1, 1), (1, 2), (2, 0)],
+        [(0, 1), (1, 1), (2, 1), (2, 2)],
+        [(1, 0), (0, 2), (1, 1), (1, 2)],
     ],
 };
 
@@ -0,0 +1,6 @@
+pub mod app;
+pub mod core;
+pub mod render;
+pub mod time;
+pub mod tbp;
+pub mod sim;
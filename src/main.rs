@@ -1,9 +1,5 @@
-mod app;
-mod core;
-mod render;
-mod time;
-
 use eframe::egui;
+use tetris_rust::app;
 
 fn main() -> eframe::Result<()> {
     let options = eframe::NativeOptions {
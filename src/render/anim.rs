@@ -1,23 +1,39 @@
 pub struct Animator {
     lock_pop_timer: f64,
     line_clear_timer: f64,
+    stack_outline_timer: f64,
+    elapsed: f64,
 }
 
+/// How long the stack outline stays visible after a line clear in the hidden-stack modes
+const STACK_OUTLINE_TIME: f64 = 1.0;
+
 impl Animator {
     pub fn new() -> Self {
         Self {
             lock_pop_timer: 0.0,
             line_clear_timer: 0.0,
+            stack_outline_timer: 0.0,
+            elapsed: 0.0,
         }
     }
 
     pub fn update(&mut self, dt: f64) {
+        self.elapsed += dt;
         if self.lock_pop_timer > 0.0 {
             self.lock_pop_timer -= dt;
         }
         if self.line_clear_timer > 0.0 {
             self.line_clear_timer -= dt;
         }
+        if self.stack_outline_timer > 0.0 {
+            self.stack_outline_timer -= dt;
+        }
+    }
+
+    /// Total animated time, used to drive pulsing effects
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
     }
 
     pub fn trigger_lock_pop(&mut self) {
@@ -28,6 +44,19 @@ impl Animator {
         self.line_clear_timer = 0.2;
     }
 
+    pub fn trigger_stack_outline(&mut self) {
+        self.stack_outline_timer = STACK_OUTLINE_TIME;
+    }
+
+    /// Opacity of the stack outline, fading out after a line clear
+    pub fn stack_outline_alpha(&self) -> f32 {
+        if self.stack_outline_timer > 0.0 {
+            (self.stack_outline_timer / STACK_OUTLINE_TIME) as f32
+        } else {
+            0.0
+        }
+    }
+
     /// Returns scale factor for lock pop animation (1.0 to 1.15 and back)
     pub fn lock_pop_scale(&self) -> f32 {
         if self.lock_pop_timer > 0.0 {
@@ -47,3 +76,9 @@ impl Animator {
         }
     }
 }
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
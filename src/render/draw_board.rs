@@ -1,26 +1,39 @@
 use eframe::egui::{self, Color32, Pos2, Rect, Rounding, Stroke, Vec2};
-use crate::core::{Board, Piece, board::{BOARD_WIDTH, BOARD_HEIGHT}};
+use crate::core::{Board, Game, GameMode, Piece, board::{BOARD_WIDTH, BOARD_HEIGHT}};
 use crate::app::settings::Settings;
-use super::{Theme, Animator};
+use super::{Theme, BoardTransform, Animator};
 
-const CELL_SIZE: f32 = 24.0;
-const BOARD_OFFSET: Vec2 = Vec2::new(50.0, 50.0);
+pub const CELL_SIZE: f32 = 24.0;
+/// Top-left corner of the well in single-player layouts
+pub const BOARD_OFFSET: Pos2 = Pos2::new(50.0, 50.0);
 
-pub fn render(
-    ui: &mut egui::Ui,
-    theme: &Theme,
-    board: &Board,
-    piece: &Piece,
-    settings: &Settings,
-    animator: &Animator,
-) {
+/// Fading mode: seconds a locked block stays fully visible, then how long it takes to disappear
+const FADE_DELAY: f64 = 3.0;
+const FADE_DURATION: f64 = 1.0;
+
+/// Size of the well on screen
+pub fn well_size() -> Vec2 {
+    Vec2::new(BOARD_WIDTH as f32 * CELL_SIZE, BOARD_HEIGHT as f32 * CELL_SIZE)
+}
+
+/// Draws a bare board for the editor: the well, its grid and the stack, with no piece in play
+pub fn render_board(ui: &mut egui::Ui, theme: &Theme, board: &Board, origin: Pos2) {
     let painter = ui.painter();
+    let view = BoardTransform::default();
+    draw_well(painter, theme, origin, true, 1);
+    for y in 0..BOARD_HEIGHT {
+        for x in 0..BOARD_WIDTH {
+            let cell = board.grid[y][x];
+            if cell != 0 {
+                draw_block(painter, theme, cell_rect(origin, view, x as i32, y as i32, 1), cell, 1.0);
+            }
+        }
+    }
+}
 
-    // Gradient background (fake gradient using multiple rectangles)
-    let board_rect = Rect::from_min_size(
-        Pos2::new(BOARD_OFFSET.x, BOARD_OFFSET.y),
-        Vec2::new(BOARD_WIDTH as f32 * CELL_SIZE, BOARD_HEIGHT as f32 * CELL_SIZE),
-    );
+/// Background, border and (optionally) grid of a well with its top-left corner at `origin`
+fn draw_well(painter: &egui::Painter, theme: &Theme, origin: Pos2, show_grid: bool, span: usize) {
+    let board_rect = Rect::from_min_size(origin, well_size());
     
     // Draw gradient background (3 bands)
     let gradient_steps = 3;
@@ -44,50 +57,200 @@ pub fn render(
     painter.rect_stroke(board_rect, Rounding::same(2.0), Stroke::new(2.0, theme.border));
 
     // Grid lines
-    if settings.show_grid {
-        for x in 0..=BOARD_WIDTH {
-            let x_pos = BOARD_OFFSET.x + x as f32 * CELL_SIZE;
+    if show_grid {
+        for x in (0..=BOARD_WIDTH).step_by(span) {
+            let x_pos = origin.x + x as f32 * CELL_SIZE;
             painter.line_segment(
-                [Pos2::new(x_pos, BOARD_OFFSET.y), Pos2::new(x_pos, BOARD_OFFSET.y + BOARD_HEIGHT as f32 * CELL_SIZE)],
+                [Pos2::new(x_pos, board_rect.min.y), Pos2::new(x_pos, board_rect.max.y)],
                 Stroke::new(1.0, theme.grid_line),
             );
         }
-        for y in 0..=BOARD_HEIGHT {
-            let y_pos = BOARD_OFFSET.y + y as f32 * CELL_SIZE;
+        for y in (0..=BOARD_HEIGHT).step_by(span) {
+            let y_pos = origin.y + y as f32 * CELL_SIZE;
             painter.line_segment(
-                [Pos2::new(BOARD_OFFSET.x, y_pos), Pos2::new(BOARD_OFFSET.x + BOARD_WIDTH as f32 * CELL_SIZE, y_pos)],
+                [Pos2::new(board_rect.min.x, y_pos), Pos2::new(board_rect.max.x, y_pos)],
                 Stroke::new(1.0, theme.grid_line),
             );
         }
     }
+}
+
+/// Draws a game's well with its top-left corner at `origin`
+pub fn render(ui: &mut egui::Ui, theme: &Theme, game: &Game, animator: &Animator, settings: &Settings, hint: Option<&Piece>, origin: Pos2) {
+    let board = &game.board;
+    let piece = &game.current_piece;
+    let painter = ui.painter();
+
+    // Big mode draws on a grid of 2×2 cells
+    let span = if game.mode == GameMode::Big { 2 } else { 1 };
+    let view = BoardTransform::for_mode(game.mode);
+
+    let board_rect = Rect::from_min_size(origin, well_size());
+    draw_well(painter, theme, origin, settings.show_grid, span);
 
     // Locked blocks with line clear animation
     let line_alpha = animator.line_clear_alpha();
-    for y in 0..BOARD_HEIGHT {
-        for x in 0..BOARD_WIDTH {
+    for y in (0..BOARD_HEIGHT).step_by(span) {
+        for x in (0..BOARD_WIDTH).step_by(span) {
             let cell = board.grid[y][x];
-            if cell != 0 {
-                draw_block(painter, theme, x as i32, y as i32, cell, line_alpha);
+            let alpha = line_alpha * stack_visibility(game.mode, board.cell_age(x, y));
+            if cell != 0 && alpha > 0.0 {
+                draw_block(painter, theme, cell_rect(origin, view, x as i32, y as i32, span), view.color(cell), alpha);
             }
         }
     }
 
+    // Hidden-stack modes briefly reveal the outline of the stack after a line clear
+    let outline_alpha = animator.stack_outline_alpha();
+    if game.mode.hides_stack() && outline_alpha > 0.0 {
+        draw_stack_outline(painter, theme, board, origin, view, outline_alpha);
+    }
+
+    // No piece is in play during the spawn delay
+    let piece_active = game.are_timer.is_none();
+
     // Ghost piece
-    if settings.show_ghost {
+    if settings.show_ghost && piece_active {
         let drop_dist = board.drop_distance(piece);
-        for (px, py) in piece.blocks() {
+        for (px, py) in piece.scaled_blocks() {
             let gy = py + drop_dist as i32;
             if gy >= 0 && gy < BOARD_HEIGHT as i32 {
-                draw_ghost_block(painter, theme, px, gy, piece.color_id);
+                draw_ghost_block(painter, theme, cell_rect(origin, view, px, gy, span), view.color(piece.color_id));
+            }
+        }
+    }
+
+    // Suggested placement, outlined so it can't be mistaken for the ghost
+    if let Some(hint) = hint.filter(|_| piece_active) {
+        for (px, py) in hint.scaled_blocks() {
+            if py >= 0 && py < BOARD_HEIGHT as i32 {
+                draw_hint_block(painter, theme, cell_rect(origin, view, px, py, span));
             }
         }
     }
 
     // Current piece with lock pop animation
     let scale = animator.lock_pop_scale();
-    for (px, py) in piece.blocks() {
-        if py >= 0 && py < BOARD_HEIGHT as i32 {
-            draw_block_scaled(painter, theme, px, py, piece.color_id, 1.0, scale, true);
+    for (px, py) in piece.scaled_blocks() {
+        if piece_active && py >= 0 && py < BOARD_HEIGHT as i32 {
+            draw_block_scaled(painter, theme, cell_rect(origin, view, px, py, span), view.color(piece.color_id), 1.0, scale, true);
+        }
+    }
+
+    // Rising garbage warning
+    if game.mode == GameMode::Survival {
+        draw_rise_warning(painter, theme, board_rect, view, game.rise_timer.warning(), animator.elapsed());
+    }
+
+    draw_garbage_meter(painter, theme, board_rect, view, game.garbage.total(), game.garbage.ready());
+}
+
+/// Red meter left of the well showing queued garbage, brighter for lines that are ready to enter
+fn draw_garbage_meter(painter: &egui::Painter, theme: &Theme, board_rect: Rect, view: BoardTransform, total: u32, ready: u32) {
+    if total == 0 {
+        return;
+    }
+
+    let track = Rect::from_min_max(
+        Pos2::new(board_rect.min.x - 14.0, board_rect.min.y),
+        Pos2::new(board_rect.min.x - 6.0, board_rect.max.y),
+    );
+    painter.rect_filled(track, Rounding::same(2.0), theme.border);
+
+    // The meter grows from the floor of the well, like the garbage itself
+    let segment = |lines: u32| {
+        let height = CELL_SIZE * lines.min(BOARD_HEIGHT as u32) as f32;
+        if view.upside_down {
+            Rect::from_min_size(track.min, Vec2::new(track.width(), height))
+        } else {
+            Rect::from_min_max(Pos2::new(track.min.x, track.max.y - height), track.max)
+        }
+    };
+    let pending = theme.danger.gamma_multiply(0.45);
+    painter.rect_filled(segment(total), Rounding::same(2.0), pending);
+    painter.rect_filled(segment(ready), Rounding::same(2.0), theme.danger);
+}
+
+/// Pulsing bar under the well that fills up as the next garbage rise approaches
+fn draw_rise_warning(painter: &egui::Painter, theme: &Theme, board_rect: Rect, view: BoardTransform, warning: f32, time: f64) {
+    if warning <= 0.0 {
+        return;
+    }
+
+    let pulse = 0.6 + 0.4 * ((time * 12.0).sin() as f32).abs();
+    let alpha = (255.0 * pulse) as u8;
+    let color = Color32::from_rgba_unmultiplied(theme.danger.r(), theme.danger.g(), theme.danger.b(), alpha);
+
+    // The bar sits on the side garbage comes from, which is the top when the view is upside down
+    let track_y = if view.upside_down { board_rect.min.y - 14.0 } else { board_rect.max.y + 8.0 };
+    let track = Rect::from_min_size(
+        Pos2::new(board_rect.min.x, track_y),
+        Vec2::new(board_rect.width(), 6.0),
+    );
+    painter.rect_filled(track, Rounding::same(3.0), theme.border);
+
+    let fill = Rect::from_min_size(track.min, Vec2::new(track.width() * warning, track.height()));
+    painter.rect_filled(fill, Rounding::same(3.0), color);
+
+    // Tint the floor of the well
+    let floor = if view.upside_down {
+        [board_rect.left_top(), board_rect.right_top()]
+    } else {
+        [board_rect.left_bottom(), board_rect.right_bottom()]
+    };
+    painter.line_segment(floor, Stroke::new(3.0, color));
+}
+
+/// Opacity of a locked block given how long ago it locked
+fn stack_visibility(mode: GameMode, age: f64) -> f32 {
+    match mode {
+        GameMode::Invisible => 0.0,
+        GameMode::Fading => (1.0 - (age - FADE_DELAY) / FADE_DURATION).clamp(0.0, 1.0) as f32,
+        _ => 1.0,
+    }
+}
+
+/// Traces every edge between a filled cell and an empty one
+fn draw_stack_outline(painter: &egui::Painter, theme: &Theme, board: &Board, origin: Pos2, view: BoardTransform, alpha: f32) {
+    let color = theme.text_primary;
+    let stroke = Stroke::new(2.0, Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), (255.0 * alpha) as u8));
+    let is_empty = |x: i32, y: i32| {
+        x >= 0
+            && x < BOARD_WIDTH as i32
+            && y < BOARD_HEIGHT as i32
+            && (y < 0 || board.grid[y as usize][x as usize] == 0)
+    };
+
+    for y in 0..BOARD_HEIGHT as i32 {
+        for x in 0..BOARD_WIDTH as i32 {
+            if board.grid[y as usize][x as usize] == 0 {
+                continue;
+            }
+            let rect = cell_rect(origin, view, x, y, 1);
+            let (min, max) = (rect.min, rect.max);
+
+            // Neighbours are checked on the canonical board, then mapped to screen sides
+            let (mut above, mut below) = (is_empty(x, y - 1), is_empty(x, y + 1));
+            let (mut left, mut right) = (is_empty(x - 1, y), is_empty(x + 1, y));
+            if view.upside_down {
+                std::mem::swap(&mut above, &mut below);
+            }
+            if view.mirror != view.upside_down {
+                std::mem::swap(&mut left, &mut right);
+            }
+
+            if above {
+                painter.line_segment([min, Pos2::new(max.x, min.y)], stroke);
+            }
+            if below {
+                painter.line_segment([Pos2::new(min.x, max.y), max], stroke);
+            }
+            if left {
+                painter.line_segment([min, Pos2::new(min.x, max.y)], stroke);
+            }
+            if right {
+                painter.line_segment([Pos2::new(max.x, min.y), max], stroke);
+            }
         }
     }
 }
@@ -100,11 +263,20 @@ fn lerp_color(a: Color32, b: Color32, t: f32) -> Color32 {
     )
 }
 
-fn draw_block(painter: &egui::Painter, theme: &Theme, x: i32, y: i32, color_id: u8, alpha: f32) {
-    draw_block_scaled(painter, theme, x, y, color_id, alpha, 1.0, false);
+/// Screen rectangle of a block whose canonical top-left cell is (x, y), `span` cells wide
+fn cell_rect(origin: Pos2, view: BoardTransform, x: i32, y: i32, span: usize) -> Rect {
+    let (x, y) = view.cell(x, y, span as i32);
+    Rect::from_min_size(
+        Pos2::new(origin.x + x as f32 * CELL_SIZE, origin.y + y as f32 * CELL_SIZE),
+        Vec2::splat(span as f32 * CELL_SIZE),
+    )
+}
+
+fn draw_block(painter: &egui::Painter, theme: &Theme, cell: Rect, color_id: u8, alpha: f32) {
+    draw_block_scaled(painter, theme, cell, color_id, alpha, 1.0, false);
 }
 
-fn draw_ghost_block(painter: &egui::Painter, theme: &Theme, x: i32, y: i32, color_id: u8) {
+fn draw_ghost_block(painter: &egui::Painter, theme: &Theme, cell: Rect, color_id: u8) {
     let base_color = theme.block_colors[color_id as usize];
     let color = Color32::from_rgba_unmultiplied(
         base_color.r(),
@@ -113,20 +285,21 @@ fn draw_ghost_block(painter: &egui::Painter, theme: &Theme, x: i32, y: i32, colo
         50,
     );
 
-    let rect = Rect::from_min_size(
-        Pos2::new(BOARD_OFFSET.x + x as f32 * CELL_SIZE + 2.0, BOARD_OFFSET.y + y as f32 * CELL_SIZE + 2.0),
-        Vec2::new(CELL_SIZE - 4.0, CELL_SIZE - 4.0),
-    );
+    let rect = cell.shrink(2.0);
 
     // Dashed outline effect for ghost
     painter.rect_stroke(rect, Rounding::same(3.0), Stroke::new(2.0, color));
 }
 
+fn draw_hint_block(painter: &egui::Painter, theme: &Theme, cell: Rect) {
+    let rect = cell.shrink(4.0);
+    painter.rect_stroke(rect, Rounding::same(2.0), Stroke::new(2.0, theme.text_primary));
+}
+
 fn draw_block_scaled(
     painter: &egui::Painter,
     theme: &Theme,
-    x: i32,
-    y: i32,
+    cell: Rect,
     color_id: u8,
     alpha: f32,
     scale: f32,
@@ -140,12 +313,10 @@ fn draw_block_scaled(
         (255.0 * alpha) as u8,
     );
 
-    let center_x = BOARD_OFFSET.x + (x as f32 + 0.5) * CELL_SIZE;
-    let center_y = BOARD_OFFSET.y + (y as f32 + 0.5) * CELL_SIZE;
-    let half_size = CELL_SIZE * 0.5 * scale;
+    let half_size = cell.width() * 0.5 * scale;
 
     let rect = Rect::from_center_size(
-        Pos2::new(center_x, center_y),
+        cell.center(),
         Vec2::new(half_size * 2.0 - 2.0, half_size * 2.0 - 2.0),
     );
 
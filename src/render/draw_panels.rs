@@ -1,9 +1,10 @@
 use eframe::egui::{self, Color32, Pos2, Rect, Rounding, Stroke, Vec2};
-use crate::app::TetrisApp;
-use crate::core::{Piece, Tetromino};
-use super::Theme;
+use crate::app::{format_time, puzzles::PuzzlePlay};
+use crate::core::{Game, GameMode, Piece, Tetromino};
+use super::{Theme, BoardTransform};
 
-pub fn render(ui: &mut egui::Ui, theme: &Theme, app: &TetrisApp) {
+pub fn render(ui: &mut egui::Ui, theme: &Theme, game: &Game, puzzle: Option<&PuzzlePlay>) {
+    let view = BoardTransform::for_mode(game.mode);
     let painter = ui.painter();
     let panel_rect = ui.available_rect_before_wrap();
     
@@ -15,39 +16,55 @@ pub fn render(ui: &mut egui::Ui, theme: &Theme, app: &TetrisApp) {
         ui.add_space(20.0);
 
         // Score section with emphasis
-        draw_stat_section(ui, theme, "SCORE", &format!("{}", app.score), 28.0, true);
+        draw_stat_section(ui, theme, "SCORE", &format!("{}", game.score), 28.0, true);
+        if game.assisted {
+            ui.label(egui::RichText::new("ASSISTED").color(theme.text_secondary).size(11.0));
+        }
+        if game.used_undo {
+            ui.label(egui::RichText::new("UNDO USED").color(theme.text_secondary).size(11.0));
+        }
         ui.add_space(15.0);
 
-        // Level
-        draw_stat_section(ui, theme, "LEVEL", &format!("{}", app.level), 22.0, false);
-        ui.add_space(12.0);
+        if game.mode == GameMode::Master {
+            // Master: level against the section stop, grade and timer
+            let level = format!("{} / {}", game.level, game.master.section_target());
+            draw_stat_section(ui, theme, "LEVEL", &level, 22.0, false);
+            ui.add_space(12.0);
+            draw_stat_section(ui, theme, "GRADE", game.master.grade(), 22.0, false);
+            ui.add_space(12.0);
+            draw_stat_section(ui, theme, "TIME", &format_time(game.master.elapsed()), 22.0, false);
+        } else if let Some(play) = puzzle {
+            // Puzzle: goal and remaining pieces
+            draw_stat_section(ui, theme, "GOAL", &play.puzzle.goal.description(), 16.0, false);
+            ui.add_space(12.0);
+            draw_stat_section(ui, theme, "PIECES LEFT", &format!("{}", game.pieces_left().unwrap_or(0)), 22.0, false);
+        } else {
+            // Level
+            draw_stat_section(ui, theme, "LEVEL", &format!("{}", game.level), 22.0, false);
+            ui.add_space(12.0);
+
+            // Lines
+            draw_stat_section(ui, theme, "LINES", &format!("{}", game.lines), 22.0, false);
+        }
+
+        draw_streaks(ui, theme, game);
 
-        // Lines
-        draw_stat_section(ui, theme, "LINES", &format!("{}", app.lines), 22.0, false);
+        if game.mode == GameMode::Cascade {
+            ui.add_space(12.0);
+            draw_stat_section(ui, theme, "BEST CHAIN", &format!("{}", game.best_chain), 22.0, false);
+        }
+
+        if game.mode == GameMode::Survival {
+            ui.add_space(12.0);
+            draw_stat_section(ui, theme, "NEXT RISE", &format!("{:.1}s", game.rise_timer.remaining()), 22.0, false);
+        }
         ui.add_space(20.0);
 
         // Divider
         draw_divider(ui, theme);
         ui.add_space(15.0);
 
-        // Next piece
-        ui.label(egui::RichText::new("NEXT").color(theme.text_secondary).size(12.0));
-        ui.add_space(5.0);
-        draw_mini_piece(ui, theme, &app.next_piece);
-        ui.add_space(15.0);
-
-        // Hold piece
-        ui.label(egui::RichText::new("HOLD").color(theme.text_secondary).size(12.0));
-        ui.add_space(5.0);
-        if let Some(ref held) = app.hold_piece {
-            if app.can_hold {
-                draw_mini_piece(ui, theme, held);
-            } else {
-                draw_mini_piece_faded(ui, theme, held);
-            }
-        } else {
-            ui.label(egui::RichText::new("—").color(theme.text_secondary).size(14.0));
-        }
+        draw_next_and_hold(ui, theme, view, game);
         ui.add_space(20.0);
 
         // Divider
@@ -65,10 +82,78 @@ pub fn render(ui: &mut egui::Ui, theme: &Theme, app: &TetrisApp) {
         draw_control_hint(ui, theme, "↑/X", "Rotate CW");
         draw_control_hint(ui, theme, "Z", "Rotate CCW");
         draw_control_hint(ui, theme, "C", "Hold");
+        if game.mode.allows_undo() {
+            let undo = if game.history.can_undo() { "Undo" } else { "Undo (none)" };
+            let redo = if game.history.can_redo() { "Redo" } else { "Redo (none)" };
+            draw_control_hint(ui, theme, "U", undo);
+            draw_control_hint(ui, theme, "Y", redo);
+        }
         draw_control_hint(ui, theme, "P/Esc", "Pause");
     });
 }
 
+/// Compact panel beside each well in versus play
+pub fn render_versus(ui: &mut egui::Ui, theme: &Theme, game: &Game, player: &str, wins: u32, wins_needed: u32) {
+    let view = BoardTransform::for_mode(game.mode);
+
+    ui.vertical(|ui| {
+        ui.label(egui::RichText::new(player).color(theme.text_primary).size(16.0).strong());
+        let marks: String = (0..wins_needed).map(|i| if i < wins { '●' } else { '○' }).collect();
+        ui.label(egui::RichText::new(marks).color(theme.danger).size(14.0));
+        ui.add_space(10.0);
+
+        draw_stat_section(ui, theme, "SCORE", &format!("{}", game.score), 18.0, true);
+        ui.add_space(6.0);
+        draw_stat_section(ui, theme, "LINES", &format!("{}", game.lines), 16.0, false);
+        ui.add_space(6.0);
+        draw_stat_section(ui, theme, "SENT", &format!("{}", game.lines_sent), 16.0, false);
+        draw_streaks(ui, theme, game);
+        ui.add_space(12.0);
+
+        draw_next_and_hold(ui, theme, view, game);
+    });
+}
+
+/// Combo and back-to-back streaks, which add to the attack
+fn draw_streaks(ui: &mut egui::Ui, theme: &Theme, game: &Game) {
+    let mut streaks = Vec::new();
+    if game.attack.combo() > 0 {
+        streaks.push(format!("COMBO ×{}", game.attack.combo()));
+    }
+    if game.attack.back_to_back() {
+        streaks.push("B2B".to_string());
+    }
+    if !streaks.is_empty() {
+        ui.add_space(8.0);
+        ui.label(egui::RichText::new(streaks.join("  ")).color(theme.danger).size(13.0).strong());
+    }
+}
+
+fn draw_next_and_hold(ui: &mut egui::Ui, theme: &Theme, view: BoardTransform, game: &Game) {
+    // Next piece
+    ui.label(egui::RichText::new("NEXT").color(theme.text_secondary).size(12.0));
+    ui.add_space(5.0);
+    if !game.has_next() {
+        ui.label(egui::RichText::new("—").color(theme.text_secondary).size(14.0));
+    } else {
+        draw_mini_piece(ui, theme, view, &game.next_piece);
+    }
+    ui.add_space(15.0);
+
+    // Hold piece
+    ui.label(egui::RichText::new("HOLD").color(theme.text_secondary).size(12.0));
+    ui.add_space(5.0);
+    if let Some(ref held) = game.hold_piece {
+        if game.can_hold {
+            draw_mini_piece(ui, theme, view, held);
+        } else {
+            draw_mini_piece_faded(ui, theme, view, held);
+        }
+    } else {
+        ui.label(egui::RichText::new("—").color(theme.text_secondary).size(14.0));
+    }
+}
+
 fn draw_stat_section(ui: &mut egui::Ui, theme: &Theme, label: &str, value: &str, value_size: f32, highlight: bool) {
     ui.label(egui::RichText::new(label).color(theme.text_secondary).size(11.0));
     
@@ -110,15 +195,15 @@ fn draw_control_hint(ui: &mut egui::Ui, theme: &Theme, key: &str, action: &str)
     });
 }
 
-fn draw_mini_piece(ui: &mut egui::Ui, theme: &Theme, piece: &Piece) {
-    draw_mini_piece_with_alpha(ui, theme, piece, 1.0);
+fn draw_mini_piece(ui: &mut egui::Ui, theme: &Theme, view: BoardTransform, piece: &Piece) {
+    draw_mini_piece_with_alpha(ui, theme, view, piece, 1.0);
 }
 
-fn draw_mini_piece_faded(ui: &mut egui::Ui, theme: &Theme, piece: &Piece) {
-    draw_mini_piece_with_alpha(ui, theme, piece, 0.4);
+fn draw_mini_piece_faded(ui: &mut egui::Ui, theme: &Theme, view: BoardTransform, piece: &Piece) {
+    draw_mini_piece_with_alpha(ui, theme, view, piece, 0.4);
 }
 
-fn draw_mini_piece_with_alpha(ui: &mut egui::Ui, theme: &Theme, piece: &Piece, alpha: f32) {
+fn draw_mini_piece_with_alpha(ui: &mut egui::Ui, theme: &Theme, view: BoardTransform, piece: &Piece, alpha: f32) {
     let size = 14.0;
     let tetromino = Tetromino::get(piece.shape);
     let blocks = &tetromino.rotations[0];
@@ -126,7 +211,11 @@ fn draw_mini_piece_with_alpha(ui: &mut egui::Ui, theme: &Theme, piece: &Piece, a
     let (response, painter) = ui.allocate_painter(Vec2::new(size * 5.0, size * 3.0), egui::Sense::hover());
     let origin = response.rect.min + Vec2::new(4.0, 0.0);
 
-    let base_color = theme.block_colors[piece.color_id as usize];
+    // Previews are flipped the same way as the board
+    let width = blocks.iter().map(|&(dx, _)| dx).max().unwrap_or(0) + 1;
+    let height = blocks.iter().map(|&(_, dy)| dy).max().unwrap_or(0) + 1;
+
+    let base_color = theme.block_colors[view.color(piece.color_id) as usize];
     let color = Color32::from_rgba_unmultiplied(
         base_color.r(),
         base_color.g(), 
@@ -134,9 +223,10 @@ fn draw_mini_piece_with_alpha(ui: &mut egui::Ui, theme: &Theme, piece: &Piece, a
         (255.0 * alpha) as u8,
     );
 
-    for (dx, dy) in blocks {
-        let x = origin.x + (*dx as f32) * size;
-        let y = origin.y + (*dy as f32) * size;
+    for &(dx, dy) in blocks {
+        let (dx, dy) = view.local(dx, dy, width, height);
+        let x = origin.x + (dx as f32) * size;
+        let y = origin.y + (dy as f32) * size;
         
         let rect = Rect::from_min_size(
             Pos2::new(x, y),
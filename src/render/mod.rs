@@ -2,6 +2,8 @@ pub mod theme;
 pub mod draw_board;
 pub mod draw_panels;
 pub mod anim;
+pub mod transform;
 
 pub use theme::Theme;
 pub use anim::Animator;
+pub use transform::BoardTransform;
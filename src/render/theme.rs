@@ -15,8 +15,9 @@ pub struct Theme {
     pub text_primary: Color32,
     pub text_secondary: Color32,
     pub grid_line: Color32,
-    pub block_colors: [Color32; 8],
+    pub block_colors: [Color32; 9],
     pub glow_color: Color32,
+    pub danger: Color32,
 }
 
 impl Theme {
@@ -47,8 +48,10 @@ impl Theme {
                 Color32::from_rgb(0x00, 0x00, 0xF0),       // 5: J - Blue
                 Color32::from_rgb(0x00, 0xF0, 0x00),       // 6: S - Green
                 Color32::from_rgb(0xF0, 0x00, 0x00),       // 7: Z - Red
+                Color32::from_rgb(0x4B, 0x55, 0x63),       // 8: Garbage - Gray
             ],
             glow_color: Color32::from_rgba_unmultiplied(0x00, 0xF0, 0xF0, 30),
+            danger: Color32::from_rgb(0xEF, 0x44, 0x44),
         }
     }
 
@@ -71,8 +74,10 @@ impl Theme {
                 Color32::from_rgb(0x40, 0x60, 0xE0),       // J - Blue
                 Color32::from_rgb(0x40, 0xE0, 0x40),       // S - Green
                 Color32::from_rgb(0xE0, 0x40, 0x40),       // Z - Red
+                Color32::from_rgb(0x3A, 0x5A, 0x5A),       // Garbage - Slate
             ],
             glow_color: Color32::from_rgba_unmultiplied(0x00, 0xD4, 0xD4, 25),
+            danger: Color32::from_rgb(0xE0, 0x50, 0x50),
         }
     }
 
@@ -95,8 +100,10 @@ impl Theme {
                 Color32::from_rgb(0x89, 0xB4, 0xFA),       // J - Blue
                 Color32::from_rgb(0xA6, 0xE3, 0xA1),       // S - Green
                 Color32::from_rgb(0xF3, 0x8B, 0xA8),       // Z - Pink
+                Color32::from_rgb(0x58, 0x5B, 0x70),       // Garbage - Overlay
             ],
             glow_color: Color32::from_rgba_unmultiplied(0xCD, 0xD6, 0xF4, 20),
+            danger: Color32::from_rgb(0xF3, 0x8B, 0xA8),
         }
     }
 }
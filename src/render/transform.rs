@@ -0,0 +1,52 @@
+use crate::core::{GameMode, board::{BOARD_WIDTH, BOARD_HEIGHT}};
+
+/// View transform for the challenge modes. The engine always plays on the canonical
+/// board; only drawing and input mapping know about the flip.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BoardTransform {
+    /// Flip horizontally; pieces trade handedness (S/Z, L/J)
+    pub mirror: bool,
+    /// Turn the view by 180°, so pieces fall towards the top of the screen
+    pub upside_down: bool,
+}
+
+impl BoardTransform {
+    pub fn for_mode(mode: GameMode) -> Self {
+        Self {
+            mirror: mode == GameMode::Mirror,
+            upside_down: mode == GameMode::UpsideDown,
+        }
+    }
+
+    fn flip_x(&self) -> bool {
+        self.mirror != self.upside_down
+    }
+
+    /// On-screen top-left cell of a block whose canonical top-left cell is (x, y), `span` cells wide
+    pub fn cell(&self, x: i32, y: i32, span: i32) -> (i32, i32) {
+        let x = if self.flip_x() { BOARD_WIDTH as i32 - x - span } else { x };
+        let y = if self.upside_down { BOARD_HEIGHT as i32 - y - span } else { y };
+        (x, y)
+    }
+
+    /// Maps a piece-local offset inside a box of the given size, for previews
+    pub fn local(&self, dx: i32, dy: i32, width: i32, height: i32) -> (i32, i32) {
+        let dx = if self.flip_x() { width - 1 - dx } else { dx };
+        let dy = if self.upside_down { height - 1 - dy } else { dy };
+        (dx, dy)
+    }
+
+    /// Color shown for a block: a mirrored piece takes the color of the piece it now looks like
+    pub fn color(&self, color_id: u8) -> u8 {
+        if !self.mirror {
+            return color_id;
+        }
+        match color_id {
+            4 => 5,
+            5 => 4,
+            6 => 7,
+            7 => 6,
+            other => other,
+        }
+    }
+}
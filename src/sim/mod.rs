@@ -0,0 +1,167 @@
+pub mod tune;
+
+use std::time::Instant;
+
+use serde::Serialize;
+use crate::core::{BagRandomizer, Game, GameEnd, GameMode, SeededRng, ai::{self, Weights}, game::FRAME};
+use crate::tbp::ExternalPlayer;
+
+/// Who plays the simulated games
+#[derive(Debug, Clone)]
+pub enum SimAi {
+    /// The built-in placement search with these weights, placing each piece instantly
+    Builtin(Weights),
+    /// An external TBP bot, started fresh for every game and run in real time
+    External(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub mode: GameMode,
+    pub ai: SimAi,
+    pub games: u32,
+    /// Game `i` uses seed `seed + i`, so runs are reproducible and comparable
+    pub seed: u64,
+    /// Games still going after this many pieces are stopped
+    pub max_pieces: u32,
+}
+
+/// Outcome of one simulated game
+#[derive(Debug, Clone, Serialize)]
+pub struct GameResult {
+    pub seed: u64,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub pieces: u32,
+    pub topped_out: bool,
+    /// Wall-clock seconds the game took to simulate
+    pub seconds: f64,
+    /// Set when an external bot failed or broke the rules
+    pub error: Option<String>,
+}
+
+/// Garbage holes are seeded apart from the pieces, as in the daily challenge
+fn garbage_seed(seed: u64) -> u64 {
+    seed ^ 0x6A09_E667_F3BC_C908
+}
+
+/// Plays one game to its end or to the piece limit
+pub fn play_game(config: &SimConfig, seed: u64) -> GameResult {
+    let mut game = Game::new(config.mode, BagRandomizer::with_seed(seed), SeededRng::new(garbage_seed(seed)));
+    let started = Instant::now();
+    let error = match &config.ai {
+        SimAi::Builtin(weights) => {
+            play_builtin(&mut game, weights, config.max_pieces);
+            None
+        }
+        SimAi::External(command) => play_external(&mut game, command, config.max_pieces),
+    };
+
+    GameResult {
+        seed,
+        score: game.score,
+        lines: game.lines,
+        level: game.level,
+        pieces: game.pieces,
+        topped_out: game.end == Some(GameEnd::ToppedOut),
+        seconds: started.elapsed().as_secs_f64(),
+        error,
+    }
+}
+
+fn play_builtin(game: &mut Game, weights: &Weights, max_pieces: u32) {
+    while game.end.is_none() && game.pieces < max_pieces {
+        if game.piece_active() {
+            let hold = ai::hold_option(game);
+            let Some(placement) = ai::best_placement(&game.board, &game.current_piece, hold.as_ref(), weights) else {
+                // Nowhere to go: the piece can't even drop
+                break;
+            };
+            for action in placement.actions {
+                game.apply(action);
+            }
+        }
+        game.update(FRAME);
+        game.take_events();
+    }
+}
+
+fn play_external(game: &mut Game, command: &str, max_pieces: u32) -> Option<String> {
+    let mut player = match ExternalPlayer::spawn(command) {
+        Ok(player) => player,
+        Err(err) => return Some(err),
+    };
+    let mut last = Instant::now();
+    while game.end.is_none() && game.pieces < max_pieces {
+        let now = Instant::now();
+        let dt = now.duration_since(last).as_secs_f64();
+        last = now;
+
+        player.update(game, dt);
+        if player.error.is_some() {
+            return player.error;
+        }
+        game.update(dt);
+        game.take_events();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    None
+}
+
+/// Runs every game of `config`, calling `progress` after each one
+pub fn run(config: &SimConfig, mut progress: impl FnMut(&GameResult)) -> Vec<GameResult> {
+    (0..config.games as u64)
+        .map(|i| {
+            let result = play_game(config, config.seed.wrapping_add(i));
+            progress(&result);
+            result
+        })
+        .collect()
+}
+
+/// Aggregate statistics of a batch of games
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Summary {
+    pub games: usize,
+    pub average_lines: f64,
+    pub average_score: f64,
+    pub average_pieces: f64,
+    pub min_score: u32,
+    pub median_score: u32,
+    pub p90_score: u32,
+    pub max_score: u32,
+    /// Share of games that ended by topping out, from 0 to 1
+    pub top_out_rate: f64,
+    /// Pieces placed per wall-clock second of simulation
+    pub pps: f64,
+    pub errors: usize,
+}
+
+impl Summary {
+    pub fn new(results: &[GameResult]) -> Self {
+        if results.is_empty() {
+            return Self::default();
+        }
+        let count = results.len() as f64;
+        let mut scores: Vec<u32> = results.iter().map(|result| result.score).collect();
+        scores.sort_unstable();
+        let percentile = |p: f64| scores[((scores.len() - 1) as f64 * p).round() as usize];
+        let pieces: u32 = results.iter().map(|result| result.pieces).sum();
+        let seconds: f64 = results.iter().map(|result| result.seconds).sum();
+
+        Self {
+            games: results.len(),
+            average_lines: results.iter().map(|result| result.lines as f64).sum::<f64>() / count,
+            average_score: scores.iter().map(|&score| score as f64).sum::<f64>() / count,
+            average_pieces: pieces as f64 / count,
+            min_score: scores[0],
+            median_score: percentile(0.5),
+            p90_score: percentile(0.9),
+            max_score: scores[scores.len() - 1],
+            top_out_rate: results.iter().filter(|result| result.topped_out).count() as f64 / count,
+            pps: if seconds > 0.0 { pieces as f64 / seconds } else { 0.0 },
+            errors: results.iter().filter(|result| result.error.is_some()).count(),
+        }
+    }
+}
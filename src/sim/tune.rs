@@ -0,0 +1,216 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::core::{GameMode, SeededRng, ai::Weights};
+use super::{play_game, SimAi, SimConfig};
+
+/// Best individuals carried over to the next generation unchanged
+const ELITE: usize = 2;
+/// Chance of each weight being nudged in a child
+const MUTATION_RATE: f64 = 0.1;
+/// Largest nudge a mutation applies, before normalizing
+const MUTATION_SIZE: f64 = 0.2;
+
+/// What the tuner optimizes for; fixed for the whole run and kept in the checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TuneSettings {
+    pub mode: GameMode,
+    pub population: usize,
+    /// Seeded games each individual plays per generation
+    pub games: u32,
+    pub max_pieces: u32,
+    /// Seed of the first generation's games; later generations play new seeds, and the
+    /// validation games the ones just before it
+    pub seed: u64,
+}
+
+/// A set of weights and the average lines it cleared
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Scored {
+    pub weights: Weights,
+    pub fitness: f64,
+}
+
+/// Everything needed to pick a run up where it stopped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub settings: TuneSettings,
+    /// Generations finished so far
+    pub generation: u32,
+    /// The population about to be evaluated
+    pub population: Vec<Weights>,
+    /// Best leader of any generation so far, scored on the validation games
+    pub best: Option<Scored>,
+    rng: SeededRng,
+}
+
+/// Genetic algorithm over evaluation weights. Each generation every individual plays the same
+/// seeded games; the fittest breed the next generation by fitness-weighted crossover and small
+/// mutations. The evaluation is linear, so weights are kept at unit length.
+pub struct Tuner {
+    pub checkpoint: Checkpoint,
+}
+
+/// Scales weights to unit length; only their direction changes which placement wins
+fn normalized(values: [f64; Weights::COUNT]) -> Weights {
+    let length = values.iter().map(|value| value * value).sum::<f64>().sqrt();
+    if length == 0.0 {
+        return Weights::from_array(values);
+    }
+    Weights::from_array(values.map(|value| value / length))
+}
+
+impl Tuner {
+    /// A fresh run: the default weights plus random individuals
+    pub fn new(settings: TuneSettings) -> Self {
+        let mut rng = SeededRng::new(settings.seed);
+        let mut population = vec![normalized(Weights::default().to_array())];
+        while population.len() < settings.population.max(ELITE + 1) {
+            population.push(normalized(std::array::from_fn(|_| rng.gen_range(-1.0..1.0))));
+        }
+        Self {
+            checkpoint: Checkpoint { settings, generation: 0, population, best: None, rng },
+        }
+    }
+
+    pub fn resume(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let checkpoint = serde_json::from_str(&text).map_err(io::Error::other)?;
+        Ok(Self { checkpoint })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(&self.checkpoint).map_err(io::Error::other)?;
+        // Write then rename, so an interrupted save never leaves a broken checkpoint
+        let partial = path.with_extension("tmp");
+        fs::write(&partial, text)?;
+        fs::rename(partial, path)
+    }
+
+    /// Average lines cleared by `weights` over this generation's games
+    fn fitness(settings: &TuneSettings, generation: u32, weights: Weights) -> f64 {
+        Self::average_lines(settings, weights, settings.seed.wrapping_add(generation as u64 * settings.games as u64))
+    }
+
+    /// Average lines cleared by `weights` over the validation games. These are the same every
+    /// generation, so unlike fitness the scores compare across generations.
+    fn validation(settings: &TuneSettings, weights: Weights) -> f64 {
+        Self::average_lines(settings, weights, settings.seed.wrapping_sub(settings.games as u64))
+    }
+
+    fn average_lines(settings: &TuneSettings, weights: Weights, seed: u64) -> f64 {
+        let config = SimConfig {
+            mode: settings.mode,
+            ai: SimAi::Builtin(weights),
+            games: settings.games,
+            seed,
+            max_pieces: settings.max_pieces,
+        };
+        let lines: u32 = (0..settings.games as u64)
+            .map(|i| play_game(&config, config.seed.wrapping_add(i)).lines)
+            .sum();
+        lines as f64 / settings.games.max(1) as f64
+    }
+
+    /// Scores the whole population on `threads` threads
+    fn evaluate(&self, threads: usize) -> Vec<Scored> {
+        let checkpoint = &self.checkpoint;
+        let next = AtomicUsize::new(0);
+        let scores = Mutex::new(vec![0.0; checkpoint.population.len()]);
+        thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&weights) = checkpoint.population.get(index) else {
+                        break;
+                    };
+                    let fitness = Self::fitness(&checkpoint.settings, checkpoint.generation, weights);
+                    scores.lock().unwrap_or_else(|err| err.into_inner())[index] = fitness;
+                });
+            }
+        });
+
+        let scores = scores.into_inner().unwrap_or_else(|err| err.into_inner());
+        checkpoint
+            .population
+            .iter()
+            .zip(scores)
+            .map(|(&weights, fitness)| Scored { weights, fitness })
+            .collect()
+    }
+
+    /// Picks the fitter of a few random individuals
+    fn tournament<'a>(rng: &mut SeededRng, ranked: &'a [Scored]) -> &'a Scored {
+        let size = (ranked.len() / 10).max(2);
+        (0..size)
+            .map(|_| &ranked[rng.gen_range(0..ranked.len())])
+            .max_by(|a, b| a.fitness.total_cmp(&b.fitness))
+            .unwrap_or(&ranked[0])
+    }
+
+    fn breed(rng: &mut SeededRng, ranked: &[Scored]) -> Weights {
+        let (a, b) = (Self::tournament(rng, ranked), Self::tournament(rng, ranked));
+        let total = a.fitness + b.fitness;
+        let share = if total > 0.0 { a.fitness / total } else { 0.5 };
+        let (a, b) = (a.weights.to_array(), b.weights.to_array());
+        let child = std::array::from_fn(|i| {
+            let value = a[i] * share + b[i] * (1.0 - share);
+            if rng.gen_bool(MUTATION_RATE) {
+                value + rng.gen_range(-MUTATION_SIZE..MUTATION_SIZE)
+            } else {
+                value
+            }
+        });
+        normalized(child)
+    }
+
+    /// Evaluates the current generation and breeds the next. Returns the generation's best.
+    /// Each generation plays different games, so the leader replaces the best so far only if
+    /// it does better on the validation games.
+    pub fn step(&mut self, threads: usize) -> Scored {
+        let mut ranked = self.evaluate(threads);
+        ranked.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+        let leader = ranked[0];
+
+        let checkpoint = &mut self.checkpoint;
+        let validated = Scored {
+            weights: leader.weights,
+            fitness: Self::validation(&checkpoint.settings, leader.weights),
+        };
+        if checkpoint.best.is_none_or(|best| validated.fitness > best.fitness) {
+            checkpoint.best = Some(validated);
+        }
+
+        let mut population: Vec<Weights> = ranked.iter().take(ELITE).map(|scored| scored.weights).collect();
+        while population.len() < ranked.len() {
+            population.push(Self::breed(&mut checkpoint.rng, &ranked));
+        }
+        checkpoint.population = population;
+        checkpoint.generation += 1;
+        leader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoint_weights_reload_bit_exact() {
+        let settings = TuneSettings { mode: GameMode::Marathon, population: 30, games: 5, max_pieces: 300, seed: 7 };
+        let mut tuner = Tuner::new(settings);
+        tuner.checkpoint.population[0].wells = -0.09492862863519565;
+
+        let text = serde_json::to_string_pretty(&tuner.checkpoint).unwrap();
+        let checkpoint: Checkpoint = serde_json::from_str(&text).unwrap();
+        for (saved, loaded) in tuner.checkpoint.population.iter().zip(&checkpoint.population) {
+            assert_eq!(saved.to_array().map(f64::to_bits), loaded.to_array().map(f64::to_bits));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+
+use crate::core::{Action, Board, Game, ai, tetromino::TetrominoShape};
+use super::messages::{to_tbp_board, BotMessage, FrontendMessage, Move, Start};
+use super::process::BotProcess;
+
+/// Seconds the bot gets to introduce itself and to accept the rules
+pub const HANDSHAKE_TIMEOUT: f64 = 5.0;
+/// Seconds the bot gets to suggest a move
+pub const SUGGEST_TIMEOUT: f64 = 2.0;
+/// Pieces shown to the bot after the current one
+const PREVIEWS: usize = 5;
+
+enum Phase {
+    AwaitingInfo,
+    AwaitingReady,
+    /// Waiting for a piece to be in play
+    Idle,
+    Thinking,
+}
+
+/// Lets an external TBP bot play a game. Each piece the bot is asked for a suggestion, and the
+/// first suggested move the engine can actually reach is played through `Game::apply`, so
+/// the bot follows the same rules as a person.
+pub struct ExternalPlayer {
+    process: BotProcess,
+    phase: Phase,
+    /// Seconds spent in the current phase
+    waited: f64,
+    /// Name the bot introduced itself with
+    pub name: Option<String>,
+    /// The queue as the bot knows it, current piece first
+    queue: Vec<TetrominoShape>,
+    hold: Option<TetrominoShape>,
+    /// Whether the bot has been sent a start message since the last stop
+    started: bool,
+    /// Board the bot expects after its last move; if the game's differs (garbage, rising
+    /// floor) the bot is restarted from the real state
+    expected: Option<Board>,
+    /// Set when the bot fails or breaks the rules; the game should end
+    pub error: Option<String>,
+}
+
+impl ExternalPlayer {
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        let process = BotProcess::spawn(command).map_err(|err| format!("could not start bot: {err}"))?;
+        Ok(Self {
+            process,
+            phase: Phase::AwaitingInfo,
+            waited: 0.0,
+            name: None,
+            queue: Vec::new(),
+            hold: None,
+            started: false,
+            expected: None,
+            error: None,
+        })
+    }
+
+    /// Advances the conversation with the bot by `dt` seconds and plays its move when it
+    /// arrives
+    pub fn update(&mut self, game: &mut Game, dt: f64) {
+        if self.error.is_some() || game.end.is_some() {
+            return;
+        }
+        if let Err(err) = self.step(game, dt) {
+            self.error = Some(err);
+        }
+    }
+
+    fn step(&mut self, game: &mut Game, dt: f64) -> Result<(), String> {
+        self.waited += dt;
+        while let Some(message) = self.process.poll()? {
+            self.receive(message, game)?;
+        }
+
+        match self.phase {
+            Phase::AwaitingInfo | Phase::AwaitingReady if self.waited > HANDSHAKE_TIMEOUT => {
+                Err("bot did not finish the handshake in time".to_string())
+            }
+            Phase::Thinking if self.waited > SUGGEST_TIMEOUT => Err("bot took too long to suggest a move".to_string()),
+            Phase::Idle if game.piece_active() => {
+                self.sync(game)?;
+                self.send(&FrontendMessage::Suggest)?;
+                self.enter(Phase::Thinking);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn receive(&mut self, message: BotMessage, game: &mut Game) -> Result<(), String> {
+        match (message, &self.phase) {
+            (BotMessage::Error { reason }, _) => Err(format!("bot error: {reason}")),
+            (BotMessage::Info { name, .. }, Phase::AwaitingInfo) => {
+                self.name = Some(name);
+                self.send(&FrontendMessage::Rules)?;
+                self.enter(Phase::AwaitingReady);
+                Ok(())
+            }
+            (BotMessage::Ready, Phase::AwaitingReady) => {
+                self.enter(Phase::Idle);
+                Ok(())
+            }
+            (BotMessage::Suggestion { moves }, Phase::Thinking) => {
+                self.play(&moves, game)?;
+                self.enter(Phase::Idle);
+                Ok(())
+            }
+            (message, _) => Err(format!("unexpected message from bot: {message:?}")),
+        }
+    }
+
+    fn enter(&mut self, phase: Phase) {
+        self.phase = phase;
+        self.waited = 0.0;
+    }
+
+    fn send(&mut self, message: &FrontendMessage) -> Result<(), String> {
+        self.process.send(message).map_err(|err| format!("could not reach bot: {err}"))
+    }
+
+    /// The current piece and the previews, as far as the randomizer can be read ahead
+    fn upcoming(game: &Game) -> Vec<TetrominoShape> {
+        let mut rng = game.rng.clone();
+        let mut pieces = vec![game.current_piece.shape, game.next_piece.shape];
+        pieces.extend((1..PREVIEWS).map(|_| rng.next()));
+        pieces
+    }
+
+    /// Brings the bot up to date before asking for a move: new previews normally, or a fresh
+    /// start when the game moved on in a way the bot couldn't follow
+    fn sync(&mut self, game: &Game) -> Result<(), String> {
+        let upcoming = Self::upcoming(game);
+        let in_step = self.started
+            && upcoming.starts_with(&self.queue)
+            && self.hold == game.hold_piece.as_ref().map(|piece| piece.shape)
+            && self
+                .expected
+                .as_ref()
+                .is_none_or(|board| board.grid == game.board.grid && board.buffer == game.board.buffer);
+
+        if in_step {
+            for &piece in &upcoming[self.queue.len()..] {
+                self.send(&FrontendMessage::NewPiece { piece })?;
+            }
+        } else {
+            if self.started {
+                self.send(&FrontendMessage::Stop)?;
+            }
+            self.send(&FrontendMessage::Start(Start {
+                hold: game.hold_piece.as_ref().map(|piece| piece.shape),
+                queue: upcoming.clone(),
+                combo: game.attack.combo(),
+                back_to_back: game.attack.back_to_back(),
+                board: to_tbp_board(&game.board),
+            }))?;
+            self.started = true;
+        }
+        self.queue = upcoming;
+        Ok(())
+    }
+
+    /// Plays the first suggested move the piece can reach. Suggestions the engine can't
+    /// reach are illegal; a bot offering nothing else loses.
+    fn play(&mut self, moves: &[Move], game: &mut Game) -> Result<(), String> {
+        let hold = ai::hold_option(game);
+        let candidates = ai::legal_placements(&game.board, &game.current_piece, hold.as_ref());
+        // Matched by the cells covered: a location has several names for the same cells (a
+        // vertical I facing east or west, say)
+        let chosen = moves.iter().find_map(|mv| {
+            let cells = mv.location.cells();
+            candidates
+                .iter()
+                .find(|placement| {
+                    let mut blocks = placement.piece.blocks();
+                    blocks.sort_unstable();
+                    placement.piece.scale == 1 && placement.piece.shape == mv.location.kind && blocks == cells
+                })
+                .map(|placement| (mv, placement))
+        });
+        let Some((mv, placement)) = chosen else {
+            return Err("bot suggested no legal move".to_string());
+        };
+
+        let mut expected = game.board.clone();
+        expected.lock_piece(&placement.piece);
+        expected.clear_lines();
+        self.expected = Some(expected);
+
+        // Follow the bot's hold: the held piece leaves the queue, and with an empty hold
+        // slot the next piece comes into play too
+        let mut queue: VecDeque<_> = std::mem::take(&mut self.queue).into();
+        let current = queue.pop_front();
+        if placement.actions.first() == Some(&Action::Hold) {
+            if self.hold.is_none() {
+                queue.pop_front();
+            }
+            self.hold = current;
+        }
+        self.queue = queue.into();
+
+        for &action in &placement.actions {
+            game.apply(action);
+        }
+        self.send(&FrontendMessage::Play { mv: *mv })
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use crate::core::{Board, Piece, board::{BOARD_WIDTH, BOARD_HEIGHT, BUFFER_HEIGHT, GARBAGE_COLOR}, tetromino::TetrominoShape};
+
+/// Rows of a TBP board; the rows above the well are always empty here
+pub const TBP_BOARD_HEIGHT: usize = 40;
+
+/// A TBP board: 40 rows of 10 cells, bottom row first. A cell holds the letter of the piece
+/// that filled it, `G` for garbage, or null when empty.
+pub type TbpBoard = Vec<Vec<Option<char>>>;
+
+/// Messages the game sends to the bot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrontendMessage {
+    Rules,
+    Start(Start),
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        mv: Move,
+    },
+    NewPiece {
+        piece: TetrominoShape,
+    },
+    Quit,
+}
+
+/// Messages the bot sends to the game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMessage {
+    Info {
+        name: String,
+        version: String,
+        author: String,
+        #[serde(default)]
+        features: Vec<String>,
+    },
+    Ready,
+    Error {
+        reason: String,
+    },
+    Suggestion {
+        /// Candidate moves, best first
+        moves: Vec<Move>,
+    },
+}
+
+/// Game state the bot starts thinking from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Start {
+    pub hold: Option<TetrominoShape>,
+    /// The current piece followed by the previews
+    pub queue: Vec<TetrominoShape>,
+    pub combo: u32,
+    pub back_to_back: bool,
+    pub board: TbpBoard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 4] = [Orientation::North, Orientation::East, Orientation::South, Orientation::West];
+
+    /// Turns a north-facing offset (y up) to face this way
+    fn rotate(self, (x, y): (i32, i32)) -> (i32, i32) {
+        match self {
+            Orientation::North => (x, y),
+            Orientation::East => (y, -x),
+            Orientation::South => (-x, -y),
+            Orientation::West => (-y, x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+/// Where a piece ends up, in SRS terms: the rotation center's column and row (row 0 at the
+/// bottom) and the way the piece faces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceLocation {
+    #[serde(rename = "type")]
+    pub kind: TetrominoShape,
+    pub orientation: Orientation,
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Move {
+    pub location: PieceLocation,
+    pub spin: Spin,
+}
+
+/// Cells of a north-facing piece around its SRS rotation center, y up
+fn north_offsets(shape: TetrominoShape) -> [(i32, i32); 4] {
+    match shape {
+        TetrominoShape::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        TetrominoShape::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        TetrominoShape::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        TetrominoShape::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+        TetrominoShape::J => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+        TetrominoShape::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        TetrominoShape::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+    }
+}
+
+/// Row of the game's board (top first) holding TBP row `y` (bottom first)
+fn board_row(y: i32) -> i32 {
+    BOARD_HEIGHT as i32 - 1 - y
+}
+
+impl PieceLocation {
+    /// Cells covered by the piece in the game's board coordinates, sorted
+    pub fn cells(&self) -> Vec<(i32, i32)> {
+        let mut cells: Vec<_> = north_offsets(self.kind)
+            .iter()
+            .map(|&offset| {
+                let (dx, dy) = self.orientation.rotate(offset);
+                (self.x + dx, board_row(self.y + dy))
+            })
+            .collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    /// The location covering the same cells as `piece`, whatever the game's own rotation
+    /// states are. Pieces larger than one cell per block have no TBP location.
+    pub fn from_piece(piece: &Piece) -> Option<Self> {
+        if piece.scale != 1 {
+            return None;
+        }
+        let mut cells = piece.blocks();
+        cells.sort_unstable();
+        let (anchor_x, anchor_y) = cells[0];
+        for orientation in Orientation::ALL {
+            for offset in north_offsets(piece.shape) {
+                // Try each cell of the oriented piece as the one sitting on the anchor
+                let (dx, dy) = orientation.rotate(offset);
+                let location = PieceLocation {
+                    kind: piece.shape,
+                    orientation,
+                    x: anchor_x - dx,
+                    y: board_row(anchor_y) - dy,
+                };
+                if location.cells() == cells {
+                    return Some(location);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The game's board as a TBP board
+pub fn to_tbp_board(board: &Board) -> TbpBoard {
+    (0..TBP_BOARD_HEIGHT as i32)
+        .map(|y| {
+            let row = board_row(y);
+            (0..BOARD_WIDTH)
+                .map(|x| {
+                    match board.cell(x, row) {
+                        0 => None,
+                        color => Some(TetrominoShape::from_color_id(color).map_or('G', |shape| shape.letter())),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// A TBP board as the game's board. Filled cells above the buffer are dropped.
+pub fn from_tbp_board(cells: &TbpBoard) -> Board {
+    let mut board = Board::new();
+    for (y, row) in cells.iter().enumerate().take(BOARD_HEIGHT + BUFFER_HEIGHT) {
+        for (x, cell) in row.iter().enumerate().take(BOARD_WIDTH) {
+            if let Some(letter) = cell {
+                let color = TetrominoShape::from_char(*letter).map_or(GARBAGE_COLOR, |shape| shape.color_id());
+                match board_row(y as i32) {
+                    row if row >= 0 => board.grid[row as usize][x] = color,
+                    row => board.buffer[(BUFFER_HEIGHT as i32 + row) as usize][x] = color,
+                }
+            }
+        }
+    }
+    board
+}
@@ -0,0 +1,7 @@
+pub mod messages;
+pub mod process;
+pub mod driver;
+
+pub use messages::{BotMessage, FrontendMessage, Move, Orientation, PieceLocation, Spin, Start};
+pub use process::BotProcess;
+pub use driver::ExternalPlayer;
@@ -0,0 +1,86 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use super::messages::{BotMessage, FrontendMessage};
+
+/// A bot running as a child process, one JSON message per line on its stdin and stdout.
+/// Messages are written and replies read on background threads so the game never blocks
+/// waiting for the bot.
+pub struct BotProcess {
+    child: Child,
+    messages: Sender<String>,
+    replies: Receiver<Result<BotMessage, String>>,
+}
+
+impl BotProcess {
+    /// Starts `command`, split on whitespace into the program and its arguments
+    pub fn spawn(command: &str) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty bot command"))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let mut stdin = child.stdin.take().ok_or_else(|| io::Error::other("bot stdin unavailable"))?;
+        let stdout = child.stdout.take().ok_or_else(|| io::Error::other("bot stdout unavailable"))?;
+        let (messages, outgoing) = mpsc::channel();
+        thread::spawn(move || {
+            for line in outgoing {
+                if writeln!(stdin, "{line}").and_then(|_| stdin.flush()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let reply = serde_json::from_str(&line).map_err(|err| format!("bad message from bot: {err}"));
+                if sender.send(reply).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self { child, messages, replies })
+    }
+
+    /// Queues `message` for the bot. Errors once the bot has stopped reading its input.
+    pub fn send(&mut self, message: &FrontendMessage) -> io::Result<()> {
+        let line = serde_json::to_string(message).map_err(io::Error::other)?;
+        self.messages
+            .send(line)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "bot closed its input"))
+    }
+
+    /// The next reply if one has arrived. Errors when the bot sent something unreadable or
+    /// closed its output.
+    pub fn poll(&mut self) -> Result<Option<BotMessage>, String> {
+        match self.replies.try_recv() {
+            Ok(reply) => reply.map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err("bot exited".to_string()),
+        }
+    }
+}
+
+impl Drop for BotProcess {
+    fn drop(&mut self) {
+        // Ask politely, then make sure the process doesn't outlive the game
+        let _ = self.send(&FrontendMessage::Quit);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
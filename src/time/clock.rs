@@ -1,5 +1,7 @@
+use serde::{Deserialize, Serialize};
 use crate::core::Rules;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Clock {
     accumulator: f64,
     interval: f64,
@@ -17,14 +19,19 @@ impl Clock {
         self.interval = Rules::gravity_interval(level);
     }
 
-    /// Returns true when a tick should occur
-    pub fn tick(&mut self, dt: f64) -> bool {
+    /// Overrides the gravity interval, for modes with their own speed curve
+    pub fn set_interval(&mut self, interval: f64) {
+        self.interval = interval;
+    }
+
+    /// Returns how many rows the piece should fall this frame
+    pub fn ticks(&mut self, dt: f64) -> u32 {
         self.accumulator += dt;
-        if self.accumulator >= self.interval {
+        let mut ticks = 0;
+        while self.accumulator >= self.interval {
             self.accumulator -= self.interval;
-            true
-        } else {
-            false
+            ticks += 1;
         }
+        ticks
     }
 }
@@ -1,3 +1,5 @@
 pub mod clock;
+pub mod rise;
 
 pub use clock::Clock;
+pub use rise::RiseTimer;
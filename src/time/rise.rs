@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use crate::core::Rules;
+
+/// Seconds before a rise during which the warning indicator is shown
+const WARNING_TIME: f64 = 1.5;
+
+/// Drives the rising garbage rows of Survival mode
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RiseTimer {
+    accumulator: f64,
+    interval: f64,
+    rises: u32,
+}
+
+impl RiseTimer {
+    pub fn new() -> Self {
+        Self {
+            accumulator: 0.0,
+            interval: Rules::rise_interval(0),
+            rises: 0,
+        }
+    }
+
+    /// Returns true when a garbage row should rise
+    pub fn tick(&mut self, dt: f64) -> bool {
+        self.accumulator += dt;
+        if self.accumulator >= self.interval {
+            self.accumulator -= self.interval;
+            self.rises += 1;
+            self.interval = Rules::rise_interval(self.rises);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Seconds left until the next rise
+    pub fn remaining(&self) -> f64 {
+        (self.interval - self.accumulator).max(0.0)
+    }
+
+    /// Warning intensity: 0.0 outside the warning window, rising to 1.0 at the moment of the rise
+    pub fn warning(&self) -> f32 {
+        let remaining = self.remaining();
+        if remaining < WARNING_TIME {
+            (1.0 - remaining / WARNING_TIME) as f32
+        } else {
+            0.0
+        }
+    }
+
+    pub fn rises(&self) -> u32 {
+        self.rises
+    }
+}
+
+impl Default for RiseTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}